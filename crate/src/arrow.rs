@@ -0,0 +1,281 @@
+//! Conversion between values and Apache Arrow IPC, enabled by the `arrow` feature.
+
+use std::{io::Cursor, sync::Arc};
+
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_ipc::{
+    reader::{FileReader, StreamReader},
+    writer::StreamWriter,
+};
+use arrow_schema::{DataType, Field, Schema};
+use uiua::{Boxed, Uiua, Value};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError};
+
+use crate::value::{build_map, NativeValueWrapper};
+
+/// The largest integer below which every integer can be represented exactly as an `f64`.
+const MAX_SAFE_INTEGER: u64 = 1 << 53;
+
+#[wasm_bindgen]
+impl NativeValueWrapper {
+    /// Encode a map of column names to columns, or a rank 2 numeric or boxed array, as an
+    /// Arrow IPC stream. Columns of numbers become `Float64` (or `UInt8` for bytes), and
+    /// columns of strings become `Utf8`.
+    #[wasm_bindgen(js_name = toArrow)]
+    pub fn to_arrow(&self) -> Result<Vec<u8>, JsError> {
+        value_to_arrow(&self.to_value()).map_err(|err| JsError::new(&err))
+    }
+
+    /// Decode an Arrow IPC stream or file into a map of column names to columns.
+    #[wasm_bindgen(js_name = fromArrow)]
+    pub fn from_arrow(bytes: Vec<u8>) -> Result<NativeValueWrapper, JsError> {
+        arrow_to_value(&bytes, &Uiua::with_safe_sys())
+            .map(NativeValueWrapper::new)
+            .map_err(|err| JsError::new(&err))
+    }
+}
+
+/// A column of a table being converted to Arrow.
+enum TableColumn {
+    Numbers(Vec<f64>),
+    Bytes(Vec<u8>),
+    Cells(Vec<Value>),
+}
+
+/// Splits a value into named columns: a map of column names to columns,
+/// or a rank 2 numeric or boxed array whose columns are named by their index.
+fn table_columns(value: &Value) -> Result<Vec<(String, TableColumn)>, String> {
+    if let Some(keys) = &value.meta().map_keys {
+        let keys = keys.clone().normalized();
+        return keys
+            .rows()
+            .zip(value.rows())
+            .map(|(key, column)| {
+                let name = match key.unboxed() {
+                    Value::Char(name) => name.elements().collect::<String>(),
+                    key => key.show(),
+                };
+                Ok((name, value_column(column.unboxed())?))
+            })
+            .collect();
+    }
+
+    let (rows, columns) = match value.shape() {
+        [rows, columns] => (*rows, *columns),
+        _ => return Err("Only maps of columns and rank 2 arrays can be converted to Arrow".into()),
+    };
+
+    let strided = |j: usize| (0..rows).map(move |i| i * columns + j);
+    let table = (0..columns).map(|j| {
+        let column = match value {
+            Value::Num(array) => TableColumn::Numbers(strided(j).map(|i| array.data[i]).collect()),
+            Value::Byte(array) => TableColumn::Bytes(strided(j).map(|i| array.data[i]).collect()),
+            Value::Box(array) => {
+                TableColumn::Cells(strided(j).map(|i| array.data[i].0.clone()).collect())
+            }
+            _ => {
+                return Err(format!(
+                    "{} arrays can't be converted to Arrow",
+                    value.type_name()
+                ))
+            }
+        };
+        Ok((j.to_string(), column))
+    });
+
+    table.collect()
+}
+
+fn value_column(value: Value) -> Result<TableColumn, String> {
+    match value {
+        Value::Num(array) if array.rank() == 1 => Ok(TableColumn::Numbers(array.data.to_vec())),
+        Value::Byte(array) if array.rank() == 1 => Ok(TableColumn::Bytes(array.data.to_vec())),
+        Value::Box(array) if array.rank() == 1 => Ok(TableColumn::Cells(
+            array.data.iter().map(|boxed| boxed.0.clone()).collect(),
+        )),
+        value @ Value::Char(_) if value.rank() == 2 => {
+            Ok(TableColumn::Cells(value.rows().collect()))
+        }
+        value => Err(format!(
+            "A column must be a list of numbers or strings, but it has shape {:?}",
+            value.shape().to_vec()
+        )),
+    }
+}
+
+fn column_to_arrow(name: &str, column: TableColumn) -> Result<ArrayRef, String> {
+    use arrow_array::{Float64Array, StringArray, UInt8Array};
+
+    let cells = match column {
+        TableColumn::Numbers(numbers) => return Ok(Arc::new(Float64Array::from(numbers))),
+        TableColumn::Bytes(bytes) => return Ok(Arc::new(UInt8Array::from(bytes))),
+        TableColumn::Cells(cells) => cells,
+    };
+
+    let numbers = cells
+        .iter()
+        .map(|cell| match cell {
+            Value::Num(array) if array.rank() == 0 => Some(array.data[0]),
+            Value::Byte(array) if array.rank() == 0 => Some(array.data[0] as f64),
+            _ => None,
+        })
+        .collect::<Option<Vec<f64>>>();
+
+    if let Some(numbers) = numbers {
+        return Ok(Arc::new(Float64Array::from(numbers)));
+    }
+
+    let strings = cells
+        .iter()
+        .map(|cell| match cell {
+            Value::Char(array) if array.rank() <= 1 => Some(array.elements().collect::<String>()),
+            _ => None,
+        })
+        .collect::<Option<Vec<String>>>();
+
+    match strings {
+        Some(strings) => Ok(Arc::new(StringArray::from(strings))),
+        None => Err(format!(
+            "Column {name} must contain only scalar numbers or only strings"
+        )),
+    }
+}
+
+fn value_to_arrow(value: &Value) -> Result<Vec<u8>, String> {
+    let mut fields = Vec::new();
+    let mut arrays = Vec::new();
+
+    for (name, column) in table_columns(value)? {
+        let array = column_to_arrow(&name, column)?;
+        fields.push(Field::new(name, array.data_type().clone(), false));
+        arrays.push(array);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(|err| err.to_string())?;
+
+    let mut writer = StreamWriter::try_new(Vec::new(), &schema).map_err(|err| err.to_string())?;
+    writer.write(&batch).map_err(|err| err.to_string())?;
+    writer.finish().map_err(|err| err.to_string())?;
+    writer.into_inner().map_err(|err| err.to_string())
+}
+
+/// Reads the columns of an Arrow IPC stream or file into a map of column names to columns, both
+/// of which are boxed.
+///
+/// Numeric and boolean columns become numbers, or bytes if they are `UInt8` or `Boolean` and
+/// have no missing values. Missing numbers and booleans become NaN, and missing strings become
+/// empty strings. 64-bit integers that can't be represented exactly as numbers are an error.
+fn arrow_to_value(bytes: &[u8], env: &Uiua) -> Result<Value, String> {
+    let (schema, batches) = if bytes.starts_with(b"ARROW1") {
+        let reader =
+            FileReader::try_new(Cursor::new(bytes), None).map_err(|err| err.to_string())?;
+        let schema = reader.schema();
+        (schema, reader.collect::<Result<Vec<_>, _>>())
+    } else {
+        let reader =
+            StreamReader::try_new(Cursor::new(bytes), None).map_err(|err| err.to_string())?;
+        let schema = reader.schema();
+        (schema, reader.collect::<Result<Vec<_>, _>>())
+    };
+    let batches = batches.map_err(|err| err.to_string())?;
+
+    let mut names = Vec::new();
+    let mut columns = Vec::new();
+
+    for (index, field) in schema.fields().iter().enumerate() {
+        let arrays = batches
+            .iter()
+            .map(|batch| batch.column(index).as_ref())
+            .collect::<Vec<&dyn Array>>();
+        let column = arrow_column_to_value(field.data_type(), &arrays)
+            .map_err(|err| format!("Column {}: {err}", field.name()))?;
+
+        names.push(Value::from(field.name().clone()));
+        columns.push(column);
+    }
+
+    build_map(names, columns, env).map_err(|err| err.to_string())
+}
+
+fn arrow_column_to_value(data_type: &DataType, arrays: &[&dyn Array]) -> Result<Value, String> {
+    use arrow_array::{cast::AsArray, types::*};
+
+    macro_rules! numbers {
+        ($type:ty) => {
+            arrays
+                .iter()
+                .flat_map(|array| array.as_primitive::<$type>().iter())
+                .map(|n| n.map(|n| n as f64).unwrap_or(f64::NAN))
+                .collect::<Value>()
+        };
+    }
+
+    let has_nulls = arrays.iter().any(|array| array.null_count() > 0);
+
+    let value = match data_type {
+        DataType::Float64 => numbers!(Float64Type),
+        DataType::Float32 => numbers!(Float32Type),
+        DataType::Int8 => numbers!(Int8Type),
+        DataType::Int16 => numbers!(Int16Type),
+        DataType::Int32 => numbers!(Int32Type),
+        DataType::UInt16 => numbers!(UInt16Type),
+        DataType::UInt32 => numbers!(UInt32Type),
+        DataType::Int64 => {
+            let numbers = arrays
+                .iter()
+                .flat_map(|array| array.as_primitive::<Int64Type>().iter())
+                .map(|n| match n {
+                    Some(n) if n.unsigned_abs() > MAX_SAFE_INTEGER => Err(imprecise_integer(n)),
+                    Some(n) => Ok(n as f64),
+                    None => Ok(f64::NAN),
+                });
+            numbers.collect::<Result<Value, String>>()?
+        }
+        DataType::UInt64 => {
+            let numbers = arrays
+                .iter()
+                .flat_map(|array| array.as_primitive::<UInt64Type>().iter())
+                .map(|n| match n {
+                    Some(n) if n > MAX_SAFE_INTEGER => Err(imprecise_integer(n)),
+                    Some(n) => Ok(n as f64),
+                    None => Ok(f64::NAN),
+                });
+            numbers.collect::<Result<Value, String>>()?
+        }
+        DataType::UInt8 if has_nulls => numbers!(UInt8Type),
+        DataType::UInt8 => arrays
+            .iter()
+            .flat_map(|array| array.as_primitive::<UInt8Type>().values().iter().copied())
+            .collect::<Value>(),
+        DataType::Boolean if has_nulls => arrays
+            .iter()
+            .flat_map(|array| array.as_boolean().iter())
+            .map(|b| b.map(|b| b as u8 as f64).unwrap_or(f64::NAN))
+            .collect::<Value>(),
+        DataType::Boolean => arrays
+            .iter()
+            .flat_map(|array| array.as_boolean().values().iter())
+            .map(|b| b as u8)
+            .collect::<Value>(),
+        DataType::Utf8 => arrays
+            .iter()
+            .flat_map(|array| array.as_string::<i32>().iter())
+            .map(|s| Boxed(Value::from(s.unwrap_or_default().to_string())))
+            .collect::<uiua::Array<Boxed>>()
+            .into(),
+        DataType::LargeUtf8 => arrays
+            .iter()
+            .flat_map(|array| array.as_string::<i64>().iter())
+            .map(|s| Boxed(Value::from(s.unwrap_or_default().to_string())))
+            .collect::<uiua::Array<Boxed>>()
+            .into(),
+        data_type => return Err(format!("the type {data_type} is not supported")),
+    };
+
+    Ok(value)
+}
+
+fn imprecise_integer(n: impl std::fmt::Display) -> String {
+    format!("the integer {n} can't be represented exactly as a number")
+}
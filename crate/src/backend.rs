@@ -1,16 +1,22 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-use js_sys::{Function, JsString, Reflect};
-use uiua::SysBackend;
+use js_sys::{Date, Function, JsString, Promise, Reflect};
+use uiua::{GitTarget, SysBackend};
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
-use crate::runtime::JsFunctionWrapper;
+use crate::{modules::ModuleLoader, runtime::JsFunctionWrapper};
 
 #[wasm_bindgen]
 #[derive(Default, Clone, Debug)]
 pub struct ExternalBackendHandlers {
     print_str_stdout_handler: Option<JsFunctionWrapper>,
     print_str_stderr_handler: Option<JsFunctionWrapper>,
+    now_handler: Option<JsFunctionWrapper>,
+    sleep_handler: Option<JsFunctionWrapper>,
+    virtual_time_start: Option<f64>,
 }
 
 #[wasm_bindgen]
@@ -24,12 +30,40 @@ impl ExternalBackendHandlers {
         self.print_str_stderr_handler = Some(JsFunctionWrapper(handler));
         self
     }
+
+    pub fn with_now_handler(mut self, handler: Function) -> Self {
+        self.now_handler = Some(JsFunctionWrapper(handler));
+        self
+    }
+
+    /// The handler has to block until the time has passed, since Uiua code runs synchronously.
+    /// Returning a promise is an error, as there is no way to wait for it.
+    pub fn with_sleep_handler(mut self, handler: Function) -> Self {
+        self.sleep_handler = Some(JsFunctionWrapper(handler));
+        self
+    }
+
+    /// Use a simulated clock starting at `start` seconds, which is only advanced by sleeping.
+    ///
+    /// Uiua measures the execution limit with the same clock, so the two can't be combined.
+    pub fn with_virtual_time(mut self, start: f64) -> Self {
+        self.virtual_time_start = Some(start);
+        self
+    }
+}
+
+impl ExternalBackendHandlers {
+    pub(crate) fn uses_virtual_time(&self) -> bool {
+        self.virtual_time_start.is_some()
+    }
 }
 
 #[derive(Default, Clone, Debug)]
 pub(crate) struct CustomBackend {
     stdout: Arc<Mutex<Vec<u8>>>,
     stderr: Arc<Mutex<Vec<u8>>>,
+    virtual_time: Option<Arc<Mutex<f64>>>,
+    modules: ModuleLoader,
     backend: ExternalBackendHandlers,
 }
 
@@ -39,9 +73,16 @@ impl CustomBackend {
     }
 
     pub fn set_backend(&mut self, backend: ExternalBackendHandlers) {
+        self.virtual_time = backend
+            .virtual_time_start
+            .map(|start| Arc::new(Mutex::new(start)));
         self.backend = backend;
     }
 
+    pub fn set_modules(&mut self, modules: ModuleLoader) {
+        self.modules = modules;
+    }
+
     pub fn stdout(&self) -> Vec<u8> {
         self.stdout.lock().unwrap().clone()
     }
@@ -51,7 +92,7 @@ impl CustomBackend {
     }
 }
 
-fn format_error(error: JsValue) -> String {
+pub(crate) fn format_error(error: JsValue) -> String {
     if let Ok(message) = Reflect::get(&error, &JsString::from("message")) {
         return message.as_string().unwrap_or("Unknown error".to_string());
     }
@@ -91,4 +132,73 @@ impl SysBackend for CustomBackend {
 
         Ok(())
     }
+
+    fn now(&self) -> f64 {
+        if let Some(time) = &self.virtual_time {
+            return *time.lock().unwrap();
+        }
+
+        if let Some(handler) = &self.backend.now_handler {
+            if let Some(now) = handler
+                .call0(&JsValue::undefined())
+                .ok()
+                .and_then(|now| now.as_f64())
+            {
+                return now;
+            }
+        }
+
+        Date::now() / 1000.0
+    }
+
+    fn sleep(&self, seconds: f64) -> Result<(), String> {
+        if let Some(time) = &self.virtual_time {
+            *time.lock().unwrap() += seconds;
+            return Ok(());
+        }
+
+        let handler = match &self.backend.sleep_handler {
+            Some(handler) => handler,
+            None => return Err("Sleeping is not supported in this environment".to_string()),
+        };
+
+        let result = handler
+            .call1(&JsValue::undefined(), &JsValue::from(seconds))
+            .map_err(format_error)?;
+
+        if result.is_instance_of::<Promise>() {
+            return Err(
+                "The sleep handler returned a promise, but it has to block until the time has passed"
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn file_exists(&self, path: &str) -> bool {
+        matches!(self.modules.load(path), Ok(Some(_)))
+    }
+
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        Ok(self.modules.load(path)?.is_some())
+    }
+
+    fn file_read_all(&self, path: &Path) -> Result<Vec<u8>, String> {
+        let path = path.to_string_lossy();
+        match self.modules.load(&path)? {
+            Some(source) => Ok(source.into_bytes()),
+            None => Err(format!("Module {path} was not found")),
+        }
+    }
+
+    fn load_git_module(&self, url: &str, target: GitTarget) -> Result<PathBuf, String> {
+        let version = match &target {
+            GitTarget::Default => None,
+            GitTarget::Branch(branch) => Some(branch.as_str()),
+            GitTarget::Commit(hash) => Some(hash.as_str()),
+        };
+
+        self.modules.load_package(url, version).map(PathBuf::from)
+    }
 }
@@ -0,0 +1,446 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+
+use uiua::{Assembly, BindingInfo, BindingKind, InputSrc};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{formatting::DocumentSpan, runtime::CompilerRef, value::NativeValueWrapper};
+
+/// Host bindings and constants that have to be defined by compiling generated code, grouped by
+/// their modules.
+///
+/// Functions with names like `Host~Fetch` are bound under a unique flat name and aliased in a
+/// module. Constants are defined with a placeholder value, which is replaced once the code is
+/// compiled, finding each constant by its path.
+#[derive(Default)]
+pub(crate) struct HostNamespace {
+    members: BTreeMap<String, HostMember>,
+    modules: BTreeMap<String, HostNamespace>,
+    functions: usize,
+}
+
+enum HostMember {
+    Function(String, Option<BindingDocs>),
+    Constant,
+}
+
+/// The flat names of namespaced host functions are this followed by a subscript number,
+/// which hides them from listings.
+const HOST_BINDING_PREFIX: &str = "HostBinding";
+
+/// Whether the name is one of the flat names namespaced host functions are bound under.
+fn is_flat_host_name(name: &str) -> bool {
+    match name.strip_prefix(HOST_BINDING_PREFIX) {
+        Some(number) => !number.is_empty() && number.chars().all(is_subscript),
+        None => false,
+    }
+}
+
+fn is_subscript(c: char) -> bool {
+    ('₀'..='₉').contains(&c)
+}
+
+fn subscript(n: usize) -> String {
+    n.to_string()
+        .chars()
+        .map(|digit| char::from_u32('₀' as u32 + digit.to_digit(10).unwrap()).unwrap())
+        .collect()
+}
+
+/// Checks a host binding name. Names that are compiled into the generated host code, like
+/// `Host~Fetch`, must consist of capitalized identifiers, while other names are bound as they are.
+pub(crate) fn validate_host_name(name: &str, generated: bool) -> Result<(), String> {
+    let is_identifier = |segment: &str| {
+        let letters = segment
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(segment.len());
+        let (letters, suffix) = segment.split_at(letters);
+        let numbered = match suffix.strip_prefix("__") {
+            Some(digits) => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+            None => suffix.chars().all(is_subscript),
+        };
+        letters.starts_with(char::is_uppercase) && numbered
+    };
+
+    if generated && !name.split('~').all(is_identifier) {
+        return Err(format!(
+            "{name} is not a valid binding name. Every part of the name has to be a capitalized \
+            identifier, like Host~Fetch"
+        ));
+    }
+
+    if is_flat_host_name(name) {
+        return Err(format!(
+            "{name} is not a valid binding name. Names like {HOST_BINDING_PREFIX}₁ are reserved"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Documentation that a host attaches to a binding.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub(crate) struct BindingDocs {
+    pub doc: Option<String>,
+    pub parameters: Option<Vec<String>>,
+    pub example: Option<String>,
+}
+
+impl BindingDocs {
+    /// The documentation as the lines of a doc comment.
+    fn comment_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if let Some(doc) = &self.doc {
+            lines.extend(doc.lines().map(|line| line.to_string()));
+        }
+
+        if let Some(parameters) = &self.parameters {
+            lines.push(format!("Parameters: {}", parameters.join(", ")));
+        }
+
+        if let Some(example) = &self.example {
+            lines.push("Example:".to_string());
+            lines.extend(example.lines().map(|line| format!("  {line}")));
+        }
+
+        lines
+    }
+}
+
+impl HostNamespace {
+    /// Adds a function by its path, returning the flat name it should be bound as.
+    pub fn insert_function(&mut self, path: &[&str], docs: Option<BindingDocs>) -> String {
+        self.functions += 1;
+        let flat_name = format!("{HOST_BINDING_PREFIX}{}", subscript(self.functions));
+        self.insert_member(path, HostMember::Function(flat_name.clone(), docs));
+        flat_name
+    }
+
+    /// Adds a constant by its path.
+    pub fn insert_constant(&mut self, path: &[&str]) {
+        self.insert_member(path, HostMember::Constant);
+    }
+
+    fn insert_member(&mut self, path: &[&str], member: HostMember) {
+        match path {
+            [] => {}
+            [name] => {
+                self.members.insert(name.to_string(), member);
+            }
+            [module, rest @ ..] => self
+                .modules
+                .entry(module.to_string())
+                .or_default()
+                .insert_member(rest, member),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty() && self.modules.is_empty()
+    }
+
+    /// Generates the Uiua code that defines the members.
+    pub fn to_source(&self) -> String {
+        let mut source = String::new();
+        self.write_source(&mut source, 0);
+        source
+    }
+
+    fn write_source(&self, source: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+
+        for (name, module) in &self.modules {
+            writeln!(source, "{indent}┌─╴{name}").unwrap();
+            module.write_source(source, depth + 1);
+            writeln!(source, "{indent}└─╴").unwrap();
+        }
+
+        for (name, member) in &self.members {
+            match member {
+                HostMember::Function(flat_name, docs) => {
+                    let lines = docs.as_ref().map(BindingDocs::comment_lines);
+                    for line in lines.unwrap_or_default() {
+                        writeln!(source, "{indent}# {line}").unwrap();
+                    }
+                    writeln!(source, "{indent}{name} ← {flat_name}").unwrap();
+                }
+                HostMember::Constant => {
+                    writeln!(source, "{indent}{name} ← 0").unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// Describes a binding registered by the host, as opposed to one defined in Uiua code.
+#[derive(serde::Serialize)]
+pub(crate) struct HostBindingInfo {
+    pub name: String,
+    pub kind: UiuaBindingKind,
+    pub signature: UiuaSignature,
+    pub doc: Option<String>,
+    pub parameters: Option<Vec<String>>,
+    pub example: Option<String>,
+}
+
+impl HostBindingInfo {
+    pub fn new(
+        name: String,
+        kind: UiuaBindingKind,
+        (inputs, outputs): (usize, usize),
+        docs: BindingDocs,
+    ) -> Self {
+        HostBindingInfo {
+            name,
+            kind,
+            signature: UiuaSignature { inputs, outputs },
+            doc: docs.doc,
+            parameters: docs.parameters,
+            example: docs.example,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct UiuaBindingInfo {
+    pub name: String,
+    pub kind: UiuaBindingKind,
+    pub signature: Option<UiuaSignature>,
+    pub comment: Option<String>,
+    pub span: DocumentSpan,
+    pub public: bool,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) enum UiuaBindingKind {
+    #[serde(rename = "constant")]
+    Constant,
+    #[serde(rename = "function")]
+    Function,
+    #[serde(rename = "module")]
+    Module,
+    #[serde(rename = "macro")]
+    Macro,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct UiuaSignature {
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+impl UiuaBindingInfo {
+    fn new(name: String, binding: &BindingInfo) -> Option<Self> {
+        let (kind, signature) = match &binding.kind {
+            BindingKind::Const(_) => (UiuaBindingKind::Constant, Some((0, 1))),
+            BindingKind::Func(function) => (
+                UiuaBindingKind::Function,
+                Some((function.sig.args, function.sig.outputs)),
+            ),
+            BindingKind::Import(_) | BindingKind::Module(_) => (UiuaBindingKind::Module, None),
+            BindingKind::IndexMacro(_) | BindingKind::CodeMacro(_) => {
+                (UiuaBindingKind::Macro, None)
+            }
+            BindingKind::Error => return None,
+        };
+
+        Some(UiuaBindingInfo {
+            name,
+            kind,
+            signature: signature.map(|(inputs, outputs)| UiuaSignature { inputs, outputs }),
+            comment: binding
+                .meta
+                .comment
+                .as_ref()
+                .map(|comment| comment.text.to_string()),
+            span: DocumentSpan::from(binding.span.clone()),
+            public: binding.public,
+        })
+    }
+}
+
+pub(crate) fn binding_name(asm: &Assembly, binding: &BindingInfo) -> String {
+    binding.span.as_str(&asm.inputs, |name| name.to_string())
+}
+
+/// The path the generated host code is compiled under, which keeps it apart from the code of the
+/// runtime, which is compiled from strings.
+pub(crate) const HOST_SOURCE_PATH: &str = "<host>";
+
+/// The indices of all bindings that are defined inside of a module.
+fn module_members(asm: &Assembly) -> HashSet<usize> {
+    asm.bindings
+        .iter()
+        .filter_map(|binding| match &binding.kind {
+            BindingKind::Module(module) => Some(module.names.values().map(|local| local.index)),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+fn is_host_source(src: &InputSrc) -> bool {
+    match src {
+        InputSrc::File(path) => AsRef::<Path>::as_ref(&**path) == Path::new(HOST_SOURCE_PATH),
+        _ => false,
+    }
+}
+
+/// Finds the index of a binding defined by the generated host code by its path, like
+/// `["Host", "Version"]`, following the members of its modules.
+pub(crate) fn host_binding_index(asm: &Assembly, path: &[&str]) -> Option<usize> {
+    let (first, rest) = path.split_first()?;
+
+    let members = module_members(asm);
+    let mut index = asm
+        .bindings
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(index, _)| !members.contains(index))
+        .find(|(_, binding)| {
+            is_host_source(&binding.span.src) && binding_name(asm, binding) == *first
+        })
+        .map(|(index, _)| index)?;
+
+    for segment in rest {
+        index = match &asm.bindings[index].kind {
+            BindingKind::Module(module) => module.names.get(*segment)?.index,
+            _ => return None,
+        };
+    }
+
+    Some(index)
+}
+
+/// Finds the bindings that are not defined inside of a module, keeping only the latest
+/// definition of each name.
+///
+/// Only bindings defined by the code given to the runtime are included. Bindings of imported files,
+/// git packages and the generated host code are not.
+pub(crate) fn top_level_bindings(asm: &Assembly) -> Vec<(String, &BindingInfo)> {
+    let module_members = module_members(asm);
+    let mut seen = HashSet::new();
+    let mut bindings = asm
+        .bindings
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(index, _)| !module_members.contains(index))
+        .filter(|(_, binding)| matches!(binding.span.src, InputSrc::Str(_)))
+        .map(|(_, binding)| (binding_name(asm, binding), binding))
+        .filter(|(name, _)| !name.is_empty() && !is_flat_host_name(name))
+        .filter(|(name, _)| seen.insert(name.clone()))
+        .collect::<Vec<(String, &BindingInfo)>>();
+
+    bindings.reverse();
+    bindings
+}
+
+#[wasm_bindgen]
+impl CompilerRef {
+    /// List the top level bindings defined in the compiled code.
+    pub fn bindings(&self) -> JsValue {
+        let asm = self.compiler.assembly();
+        let bindings = top_level_bindings(asm)
+            .into_iter()
+            .filter_map(|(name, binding)| UiuaBindingInfo::new(name, binding))
+            .collect::<Vec<UiuaBindingInfo>>();
+
+        serde_wasm_bindgen::to_value(&bindings).unwrap()
+    }
+
+    /// Get the value of a constant binding, if it has been evaluated.
+    #[wasm_bindgen(js_name = constantValue)]
+    pub fn constant_value(&self, name: String) -> Option<NativeValueWrapper> {
+        let asm = self.compiler.assembly();
+        top_level_bindings(asm)
+            .into_iter()
+            .find(|(binding_name, _)| *binding_name == name)
+            .and_then(|(_, binding)| match &binding.kind {
+                BindingKind::Const(Some(value)) => Some(NativeValueWrapper::new(value.clone())),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_generated_names() {
+        assert!(validate_host_name("Fetch", true).is_ok());
+        assert!(validate_host_name("Host~Fetch", true).is_ok());
+        assert!(validate_host_name("Host~Net~Get₂", true).is_ok());
+        assert!(validate_host_name("Host~Get__2", true).is_ok());
+
+        assert!(validate_host_name("Host~fetch", true).is_err());
+        assert!(validate_host_name("Host~~Fetch", true).is_err());
+        assert!(validate_host_name("Host~Fetch Url", true).is_err());
+        assert!(validate_host_name("Host~Get__", true).is_err());
+    }
+
+    #[test]
+    fn accepts_any_flat_name_that_is_not_reserved() {
+        assert!(validate_host_name("fetch", false).is_ok());
+        assert!(validate_host_name("Get__2", false).is_ok());
+        assert!(validate_host_name("HostBindingFetch", false).is_ok());
+
+        assert!(validate_host_name("HostBinding₁", false).is_err());
+        assert!(validate_host_name("HostBinding₁₂", true).is_err());
+    }
+
+    #[test]
+    fn gives_every_function_a_unique_flat_name() {
+        let mut namespace = HostNamespace::default();
+        let names = [
+            namespace.insert_function(&["Host", "Fetch"], None),
+            namespace.insert_function(&["HostFetch"], None),
+            namespace.insert_function(&["A", "BC"], None),
+            namespace.insert_function(&["AB", "C"], None),
+        ];
+
+        assert_eq!(
+            names,
+            [
+                "HostBinding₁",
+                "HostBinding₂",
+                "HostBinding₃",
+                "HostBinding₄"
+            ]
+        );
+        assert!(names.iter().all(|name| is_flat_host_name(name)));
+        assert_eq!(subscript(1024), "₁₀₂₄");
+    }
+
+    #[test]
+    fn generates_modules_with_documented_members() {
+        let mut namespace = HostNamespace::default();
+        let docs = BindingDocs {
+            doc: Some("Fetches a URL".to_string()),
+            parameters: Some(vec!["url".to_string()]),
+            example: None,
+        };
+        namespace.insert_function(&["Host", "Fetch"], Some(docs));
+        namespace.insert_constant(&["Host", "Version"]);
+        namespace.insert_constant(&["Pi"]);
+
+        assert_eq!(
+            namespace.to_source(),
+            "┌─╴Host\n\
+            \x20 # Fetches a URL\n\
+            \x20 # Parameters: url\n\
+            \x20 Fetch ← HostBinding₁\n\
+            \x20 Version ← 0\n\
+            └─╴\n\
+            Pi ← 0\n"
+        );
+        assert!(!namespace.is_empty());
+        assert!(HostNamespace::default().is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use uiua::{Compiler, Node, Span, TraceFrame, Uiua};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    formatting::{DocumentLocation, DocumentSpan, UiuaInputSource},
+    runtime::{to_js_error, top_level_nodes, UiuaRuntimeInternal, UiuaTraceFrame},
+    value::NativeValueWrapper,
+};
+
+/// A sequence of nodes that is being stepped through, either the top level code
+/// or the body of a function that was stepped into.
+struct DebugFrame {
+    nodes: Vec<Node>,
+    next: usize,
+    trace: Option<TraceFrame>,
+}
+
+impl DebugFrame {
+    fn new(node: Node, trace: Option<TraceFrame>) -> Self {
+        DebugFrame {
+            nodes: top_level_nodes(node),
+            next: 0,
+            trace,
+        }
+    }
+
+    fn current(&self) -> Option<&Node> {
+        self.nodes.get(self.next)
+    }
+}
+
+#[wasm_bindgen]
+pub struct DebugSessionInternal {
+    uiua: Uiua,
+    compiler: Compiler,
+    frames: Vec<DebugFrame>,
+    breakpoints: Vec<DocumentLocation>,
+}
+
+#[wasm_bindgen]
+impl DebugSessionInternal {
+    #[wasm_bindgen(js_name = setBreakpoint)]
+    pub fn set_breakpoint(&mut self, location: JsValue) -> Result<(), JsValue> {
+        let location: DocumentLocation = serde_wasm_bindgen::from_value(location)?;
+        self.breakpoints.push(location);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = removeBreakpoint)]
+    pub fn remove_breakpoint(&mut self, location: JsValue) -> Result<(), JsValue> {
+        let location: DocumentLocation = serde_wasm_bindgen::from_value(location)?;
+        self.breakpoints
+            .retain(|bp| bp.line != location.line || bp.column != location.column);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = clearBreakpoints)]
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn finished(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The span of the code that will be executed by the next step.
+    #[wasm_bindgen(getter)]
+    pub fn span(&self) -> JsValue {
+        match self.current_span() {
+            Some(span) => serde_wasm_bindgen::to_value(&DocumentSpan::from(span)).unwrap(),
+            None => JsValue::undefined(),
+        }
+    }
+
+    #[wasm_bindgen(getter, js_name = callStack)]
+    pub fn call_stack(&self) -> JsValue {
+        let frames = self
+            .frames
+            .iter()
+            .rev()
+            .filter_map(|frame| frame.trace.clone())
+            .map(UiuaTraceFrame::from)
+            .collect::<Vec<UiuaTraceFrame>>();
+
+        serde_wasm_bindgen::to_value(&frames).unwrap()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stack(&self) -> JsValue {
+        let values = self
+            .uiua
+            .stack()
+            .iter()
+            .map(|value| NativeValueWrapper::new(value.clone()))
+            .collect::<Vec<NativeValueWrapper>>();
+
+        JsValue::from(values)
+    }
+
+    /// Execute the next node, running any function calls to completion.
+    #[wasm_bindgen(js_name = stepOver)]
+    pub fn step_over(&mut self) -> Result<(), JsValue> {
+        let node = match self.advance() {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+
+        self.execute(node)
+    }
+
+    /// Execute the next node, entering the body of the function if it is a call.
+    #[wasm_bindgen(js_name = stepInto)]
+    pub fn step_into(&mut self) -> Result<(), JsValue> {
+        let node = match self.advance() {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+
+        match node {
+            Node::Call(function, span) => {
+                let body = self.compiler.assembly()[&function].clone();
+                let trace = TraceFrame {
+                    id: Some(function.id.clone()),
+                    span: self.compiler.assembly().spans[span].clone(),
+                };
+                self.frames.push(DebugFrame::new(body, Some(trace)));
+                self.pop_finished_frames();
+                Ok(())
+            }
+            node => self.execute(node),
+        }
+    }
+
+    /// Keep stepping into the code until a breakpoint is reached or the execution finishes.
+    #[wasm_bindgen(js_name = continue)]
+    pub fn continue_(&mut self) -> Result<(), JsValue> {
+        self.step_into()?;
+
+        while !self.finished() && !self.at_breakpoint() {
+            self.step_into()?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes the next node out of the current frame.
+    fn advance(&mut self) -> Option<Node> {
+        let frame = self.frames.last_mut()?;
+        let node = frame.current().cloned();
+        frame.next += 1;
+        node
+    }
+
+    fn execute(&mut self, node: Node) -> Result<(), JsValue> {
+        self.compiler.assembly_mut().root = node;
+        let result = self.uiua.run_compiler(&mut self.compiler);
+        self.compiler.assembly_mut().root.clear();
+
+        if let Err(err) = result {
+            self.frames.clear();
+            return Err(to_js_error(err));
+        }
+
+        self.pop_finished_frames();
+        Ok(())
+    }
+
+    fn pop_finished_frames(&mut self) {
+        while let Some(frame) = self.frames.last() {
+            if frame.current().is_some() {
+                break;
+            }
+            self.frames.pop();
+        }
+    }
+
+    fn current_span(&self) -> Option<Span> {
+        let node = self.frames.last()?.current()?;
+        let span = node.span()?;
+        self.compiler.assembly().spans.get(span).cloned()
+    }
+
+    fn at_breakpoint(&self) -> bool {
+        let span = match self.current_span() {
+            Some(span) => DocumentSpan::from(span),
+            None => return false,
+        };
+
+        if !matches!(span.src, UiuaInputSource::String(_)) {
+            return false;
+        }
+
+        self.breakpoints.iter().any(|bp| span.contains(bp))
+    }
+}
+
+#[wasm_bindgen(js_name = startDebugSession)]
+pub fn start_debug_session(
+    code: String,
+    initial_values: Vec<NativeValueWrapper>,
+    runtime: &UiuaRuntimeInternal,
+) -> Result<DebugSessionInternal, JsValue> {
+    let mut uiua = runtime.build_interpreter()?;
+    let mut compiler = runtime.build_compiler()?;
+
+    if let Err(err) = compiler.load_str(code.as_str()) {
+        return Err(to_js_error(err));
+    }
+
+    initial_values.into_iter().for_each(|value| {
+        uiua.push(value.to_value());
+    });
+
+    let root = compiler.assembly().root.clone();
+    compiler.assembly_mut().root.clear();
+    let mut session = DebugSessionInternal {
+        uiua,
+        compiler,
+        frames: vec![DebugFrame::new(root, None)],
+        breakpoints: Vec::new(),
+    };
+    session.pop_finished_frames();
+
+    Ok(session)
+}
@@ -0,0 +1,366 @@
+//! A compact binary representation of values, which keeps their type, shape, label, map keys
+//! and array flags, like the boolean flag of the results of comparisons.
+//!
+//! The encoding starts with the magic bytes `UIUA` and a version byte, followed by the value.
+//! A value is a type tag, the shape, the elements and then its metadata. All numbers are
+//! little-endian, and lengths are `u32`s.
+
+use uiua::{Array, ArrayFlags, Boxed, Complex, Uiua, Value};
+
+const MAGIC: &[u8; 4] = b"UIUA";
+const VERSION: u8 = 1;
+
+const TAG_BYTE: u8 = 0;
+const TAG_NUM: u8 = 1;
+const TAG_COMPLEX: u8 = 2;
+const TAG_CHAR: u8 = 3;
+const TAG_BOX: u8 = 4;
+
+const FLAG_LABEL: u8 = 1 << 0;
+const FLAG_MAP_KEYS: u8 = 1 << 1;
+const FLAG_ARRAY_FLAGS: u8 = 1 << 2;
+
+/// How deeply boxes and map keys may be nested, which keeps decoding from overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+pub(crate) fn encode_value(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    write_value(&mut bytes, value);
+    bytes
+}
+
+pub(crate) fn decode_value(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader { bytes, position: 0 };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err("The data is not an encoded Uiua value".into());
+    }
+
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(format!("Unsupported value encoding version {version}"));
+    }
+
+    let value = reader.value(&Uiua::with_safe_sys(), 0)?;
+    if reader.position != bytes.len() {
+        return Err("Unexpected data after the encoded value".into());
+    }
+
+    Ok(value)
+}
+
+fn write_u32(bytes: &mut Vec<u8>, n: usize) {
+    bytes.extend_from_slice(&(n as u32).to_le_bytes());
+}
+
+fn write_value(bytes: &mut Vec<u8>, value: &Value) {
+    let tag = match value {
+        Value::Byte(_) => TAG_BYTE,
+        Value::Num(_) => TAG_NUM,
+        Value::Complex(_) => TAG_COMPLEX,
+        Value::Char(_) => TAG_CHAR,
+        Value::Box(_) => TAG_BOX,
+    };
+    bytes.push(tag);
+
+    write_u32(bytes, value.rank());
+    for &dim in value.shape().iter() {
+        write_u32(bytes, dim);
+    }
+
+    match value {
+        Value::Byte(array) => bytes.extend_from_slice(&array.data),
+        Value::Num(array) => array
+            .data
+            .iter()
+            .for_each(|n| bytes.extend_from_slice(&n.to_le_bytes())),
+        Value::Complex(array) => array.data.iter().for_each(|c| {
+            bytes.extend_from_slice(&c.re.to_le_bytes());
+            bytes.extend_from_slice(&c.im.to_le_bytes());
+        }),
+        Value::Char(array) => array
+            .data
+            .iter()
+            .for_each(|&c| bytes.extend_from_slice(&(c as u32).to_le_bytes())),
+        Value::Box(array) => array
+            .data
+            .iter()
+            .for_each(|boxed| write_value(bytes, &boxed.0)),
+    }
+
+    let meta = value.meta();
+    let mut flags = 0;
+    if meta.label.is_some() {
+        flags |= FLAG_LABEL;
+    }
+    if meta.map_keys.is_some() {
+        flags |= FLAG_MAP_KEYS;
+    }
+    if !meta.flags.is_empty() {
+        flags |= FLAG_ARRAY_FLAGS;
+    }
+    bytes.push(flags);
+
+    if let Some(label) = &meta.label {
+        write_u32(bytes, label.len());
+        bytes.extend_from_slice(label.as_bytes());
+    }
+
+    if !meta.flags.is_empty() {
+        bytes.push(meta.flags.bits());
+    }
+
+    if let Some(keys) = &meta.map_keys {
+        write_value(bytes, &keys.clone().normalized());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("The encoded value is truncated")?;
+
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<usize, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    fn value(&mut self, env: &Uiua, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "The encoded value is nested more than {MAX_DEPTH} levels deep"
+            ));
+        }
+
+        let tag = self.u8()?;
+
+        let rank = self.u32()?;
+        let shape = (0..rank)
+            .map(|_| self.u32())
+            .collect::<Result<Vec<usize>, String>>()?;
+        let count = shape
+            .iter()
+            .try_fold(1usize, |count, &dim| count.checked_mul(dim))
+            .ok_or("The encoded shape is too large")?;
+
+        // Every element takes at least one byte, which guards against huge allocations
+        if count > self.bytes.len() - self.position {
+            return Err("The encoded value is truncated".into());
+        }
+
+        let mut value: Value = match tag {
+            TAG_BYTE => Array::new(shape.as_slice(), self.take(count)?.to_vec()).into(),
+            TAG_NUM => {
+                let data = (0..count)
+                    .map(|_| self.f64())
+                    .collect::<Result<Vec<f64>, String>>()?;
+                Array::new(shape.as_slice(), data).into()
+            }
+            TAG_COMPLEX => {
+                let data = (0..count)
+                    .map(|_| Ok(Complex::new(self.f64()?, self.f64()?)))
+                    .collect::<Result<Vec<Complex>, String>>()?;
+                Array::new(shape.as_slice(), data).into()
+            }
+            TAG_CHAR => {
+                let data = (0..count)
+                    .map(|_| {
+                        char::from_u32(self.u32()? as u32)
+                            .ok_or_else(|| "Invalid character".to_string())
+                    })
+                    .collect::<Result<Vec<char>, String>>()?;
+                Array::new(shape.as_slice(), data).into()
+            }
+            TAG_BOX => {
+                let data = (0..count)
+                    .map(|_| self.value(env, depth + 1).map(Boxed))
+                    .collect::<Result<Vec<Boxed>, String>>()?;
+                Array::new(shape.as_slice(), data).into()
+            }
+            tag => return Err(format!("Unknown value type {tag}")),
+        };
+
+        let flags = self.u8()?;
+
+        let label = if flags & FLAG_LABEL != 0 {
+            let length = self.u32()?;
+            let label = std::str::from_utf8(self.take(length)?)
+                .map_err(|_| "The encoded label is not valid UTF-8")?;
+            Some(label)
+        } else {
+            None
+        };
+
+        let array_flags = if flags & FLAG_ARRAY_FLAGS != 0 {
+            Some(ArrayFlags::from_bits_truncate(self.u8()?))
+        } else {
+            None
+        };
+
+        if flags & FLAG_MAP_KEYS != 0 {
+            let keys = self.value(env, depth + 1)?;
+            value.map(keys, env).map_err(|err| err.to_string())?;
+        }
+
+        if let Some(label) = label {
+            value.meta_mut().label = Some(label.into());
+        }
+
+        if let Some(array_flags) = array_flags {
+            value.meta_mut().flags = array_flags;
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: &Value) -> Value {
+        decode_value(&encode_value(value)).unwrap()
+    }
+
+    #[test]
+    fn round_trips_each_type() {
+        let values: Vec<Value> = vec![
+            Array::new([2, 2], vec![1.5, -2.0, f64::INFINITY, 0.0]).into(),
+            Array::new([3], vec![0u8, 1, 255]).into(),
+            Array::new([2], vec![Complex::new(1.0, -1.0), Complex::new(0.0, 2.5)]).into(),
+            Value::from("héllo".to_string()),
+            Array::new(
+                [2],
+                vec![Boxed(Value::from(1.0)), Boxed(Value::from("a".to_string()))],
+            )
+            .into(),
+        ];
+
+        for value in values {
+            let decoded = round_trip(&value);
+            assert_eq!(decoded, value);
+            assert_eq!(decoded.type_name(), value.type_name());
+            assert_eq!(decoded.shape(), value.shape());
+        }
+    }
+
+    #[test]
+    fn round_trips_label_and_map_keys() {
+        let env = Uiua::with_safe_sys();
+        let keys: Value = Array::new(
+            [2],
+            vec![
+                Boxed(Value::from("a".to_string())),
+                Boxed(Value::from("b".to_string())),
+            ],
+        )
+        .into();
+        let mut value: Value = Array::new([2], vec![1.0, 2.0]).into();
+        value.map(keys, &env).unwrap();
+        value.meta_mut().label = Some("name".into());
+
+        let decoded = round_trip(&value);
+        assert_eq!(decoded, value);
+        assert_eq!(decoded.meta().label.as_deref(), Some("name"));
+        assert!(decoded.meta().map_keys.is_some());
+    }
+
+    #[test]
+    fn round_trips_array_flags() {
+        let mut value: Value = Array::new([2], vec![0u8, 1]).into();
+        value.meta_mut().flags = ArrayFlags::BOOLEAN;
+
+        assert_eq!(round_trip(&value).meta().flags, ArrayFlags::BOOLEAN);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let bytes = encode_value(&Array::new([3], vec![1.0, 2.0, 3.0]).into());
+
+        for length in 0..bytes.len() {
+            assert!(decode_value(&bytes[..length]).is_err(), "length {length}");
+        }
+    }
+
+    #[test]
+    fn rejects_corrupted_headers_and_trailing_data() {
+        let bytes = encode_value(&Value::from(1.0));
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(decode_value(&wrong_magic).is_err());
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[MAGIC.len()] = VERSION + 1;
+        assert!(decode_value(&wrong_version).is_err());
+
+        let mut wrong_tag = bytes.clone();
+        wrong_tag[MAGIC.len() + 1] = 42;
+        assert!(decode_value(&wrong_tag).is_err());
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(decode_value(&trailing).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_characters() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(TAG_CHAR);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&0xD800u32.to_le_bytes());
+        bytes.push(0);
+
+        assert!(decode_value(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_huge_shapes() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(TAG_NUM);
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(decode_value(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_deeply_nested_boxes() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        for _ in 0..100_000 {
+            bytes.push(TAG_BOX);
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+        }
+
+        assert!(decode_value(&bytes).is_err());
+    }
+}
@@ -78,6 +78,13 @@ pub struct DocumentSpan {
 }
 
 impl DocumentSpan {
+    pub fn contains(&self, location: &DocumentLocation) -> bool {
+        let start = (self.from.line, self.from.column);
+        let end = (self.to.line, self.to.column);
+        let location = (location.line, location.column);
+        start <= location && location <= end
+    }
+
     fn fix_column(&self) -> Self {
         DocumentSpan {
             src: self.src.clone().into(),
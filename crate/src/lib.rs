@@ -1,5 +1,11 @@
+#[cfg(feature = "arrow")]
+mod arrow;
 mod backend;
+mod bindings;
+mod debugger;
+mod encoding;
 mod formatting;
+mod modules;
 mod runtime;
 mod value;
 
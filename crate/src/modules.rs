@@ -0,0 +1,247 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use wasm_bindgen::JsValue;
+
+use crate::{backend::format_error, runtime::JsFunctionWrapper};
+
+/// Provides the source of imported modules, either from registered files or from a host callback.
+#[derive(Default, Clone, Debug)]
+pub(crate) struct ModuleLoader {
+    files: Arc<HashMap<String, String>>,
+    packages: Arc<BTreeSet<String>>,
+    resolver: Option<JsFunctionWrapper>,
+}
+
+impl ModuleLoader {
+    pub fn add_file(&mut self, path: &str, source: String) {
+        Arc::make_mut(&mut self.files).insert(normalize_path(path), source);
+    }
+
+    /// Registers the files of a git package, which are placed in the same directory that the
+    /// compiler would otherwise clone the repository into.
+    pub fn add_package(
+        &mut self,
+        url: &str,
+        version: Option<&str>,
+        files: HashMap<String, String>,
+    ) -> Result<(), String> {
+        // Imports of a package always start from its `lib.ua`
+        if !files.keys().any(|path| normalize_path(path) == "lib.ua") {
+            return Err(format!(
+                "Package {} has no lib.ua file to import",
+                package_name(url, version)
+            ));
+        }
+
+        let directory = package_directory(url, version);
+
+        for (path, source) in files {
+            self.add_file(&format!("{directory}/{path}"), source);
+        }
+
+        Arc::make_mut(&mut self.packages).insert(directory);
+        Ok(())
+    }
+
+    /// Registers a file that was loaded by a compiler before it was serialized. If it is the entry
+    /// point of a git package, the package is added to the cache as well.
+    pub fn add_restored_file(&mut self, path: &str, source: String) {
+        let path = normalize_path(path);
+
+        if path.starts_with("uiua-modules/") {
+            if let Some(directory) = path.strip_suffix("/lib.ua") {
+                Arc::make_mut(&mut self.packages).insert(directory.to_string());
+            }
+        }
+
+        self.add_file(&path, source);
+    }
+
+    /// Returns the path of the entry point of a cached git package.
+    pub fn load_package(&self, url: &str, version: Option<&str>) -> Result<String, String> {
+        let directory = package_directory(url, version);
+
+        if self.packages.contains(&directory) {
+            return Ok(format!("{directory}/lib.ua"));
+        }
+
+        let requested = package_name(url, version);
+
+        if self.packages.is_empty() {
+            return Err(format!(
+                "Package {requested} is not in the package cache, which is empty"
+            ));
+        }
+
+        let cached = self
+            .packages
+            .iter()
+            .map(|directory| directory.trim_start_matches("uiua-modules/"))
+            .collect::<Vec<&str>>()
+            .join(", ");
+
+        Err(format!(
+            "Package {requested} is not in the package cache. Cached packages: {cached}"
+        ))
+    }
+
+    pub fn set_resolver(&mut self, resolver: JsFunctionWrapper) {
+        self.resolver = Some(resolver);
+    }
+
+    /// Looks up the source of a module, returning `None` if it is not known.
+    pub fn load(&self, path: &str) -> Result<Option<String>, String> {
+        let path = normalize_path(path);
+
+        for candidate in candidate_paths(&path) {
+            if let Some(source) = self.files.get(&candidate) {
+                return Ok(Some(source.clone()));
+            }
+        }
+
+        if let Some(resolver) = &self.resolver {
+            let source = resolver
+                .call1(&JsValue::undefined(), &JsValue::from(path.as_str()))
+                .map_err(format_error)?;
+
+            return Ok(source.as_string());
+        }
+
+        Ok(None)
+    }
+}
+
+fn package_name(url: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{url} ({version})"),
+        None => url.to_string(),
+    }
+}
+
+fn package_directory(url: &str, version: Option<&str>) -> String {
+    let url = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+
+    match version {
+        Some(version) => format!("uiua-modules/{url}@{version}"),
+        None => format!("uiua-modules/{url}"),
+    }
+}
+
+/// The compiler adds a `.ua` extension to imports without one, so modules may be registered either way.
+fn candidate_paths(path: &str) -> Vec<String> {
+    match path.strip_suffix(".ua") {
+        Some(stripped) => vec![path.to_string(), stripped.to_string()],
+        None => vec![path.to_string()],
+    }
+}
+
+/// Resolves `.` and `..` components and unifies separators, so `./lib/../a.ua` and `a.ua` match.
+pub(crate) fn normalize_path(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+
+    for part in path.split(|c| c == '/' || c == '\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(normalize_path("a.ua"), "a.ua");
+        assert_eq!(normalize_path("./lib/../a.ua"), "a.ua");
+        assert_eq!(normalize_path("lib\\nested//b.ua"), "lib/nested/b.ua");
+        assert_eq!(normalize_path("/lib/./c.ua"), "lib/c.ua");
+        assert_eq!(normalize_path("../../d.ua"), "d.ua");
+    }
+
+    #[test]
+    fn loads_files_with_or_without_extension() {
+        let mut loader = ModuleLoader::default();
+        loader.add_file("./lib/a.ua", "A ← 1".into());
+        loader.add_file("b", "B ← 2".into());
+
+        assert_eq!(loader.load("lib/a.ua"), Ok(Some("A ← 1".into())));
+        assert_eq!(loader.load("lib/x/../a.ua"), Ok(Some("A ← 1".into())));
+        assert_eq!(loader.load("b.ua"), Ok(Some("B ← 2".into())));
+        assert_eq!(loader.load("c.ua"), Ok(None));
+    }
+
+    #[test]
+    fn places_packages_where_the_compiler_clones_them() {
+        assert_eq!(
+            package_directory("https://github.com/user/lib.git/", None),
+            "uiua-modules/github.com/user/lib"
+        );
+        assert_eq!(
+            package_directory("http://github.com/user/lib", Some("main")),
+            "uiua-modules/github.com/user/lib@main"
+        );
+    }
+
+    #[test]
+    fn loads_cached_packages() {
+        let mut loader = ModuleLoader::default();
+        let files = HashMap::from([
+            ("lib.ua".to_string(), "X ← 1".to_string()),
+            ("src/util.ua".to_string(), "Y ← 2".to_string()),
+        ]);
+        loader
+            .add_package("https://github.com/user/lib", Some("v1"), files)
+            .unwrap();
+
+        let entry = loader
+            .load_package("https://github.com/user/lib", Some("v1"))
+            .unwrap();
+        assert_eq!(entry, "uiua-modules/github.com/user/lib@v1/lib.ua");
+        assert_eq!(loader.load(&entry), Ok(Some("X ← 1".into())));
+
+        let missing = loader
+            .load_package("https://github.com/user/other", None)
+            .unwrap_err();
+        assert!(missing.contains("https://github.com/user/other"));
+        assert!(missing.contains("github.com/user/lib@v1"));
+    }
+
+    #[test]
+    fn rejects_packages_without_an_entry_point() {
+        let mut loader = ModuleLoader::default();
+        let files = HashMap::from([("util.ua".to_string(), "Y ← 2".to_string())]);
+
+        let error = loader
+            .add_package("https://github.com/user/lib", None, files)
+            .unwrap_err();
+        assert!(error.contains("https://github.com/user/lib"));
+        assert!(loader
+            .load_package("https://github.com/user/lib", None)
+            .is_err());
+    }
+
+    #[test]
+    fn restores_packages_from_their_files() {
+        let mut loader = ModuleLoader::default();
+        loader.add_restored_file("uiua-modules/github.com/user/lib/lib.ua", "X ← 1".into());
+
+        assert_eq!(
+            loader.load_package("https://github.com/user/lib", None),
+            Ok("uiua-modules/github.com/user/lib/lib.ua".into())
+        );
+    }
+}
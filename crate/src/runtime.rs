@@ -1,20 +1,36 @@
-use std::time::Duration;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    time::Duration,
+};
 
-use js_sys::Function;
-use uiua::{Compiler, Diagnostic, Span, TraceFrame, Uiua, UiuaError, Value};
+use js_sys::{Array, Function, Object, Reflect};
+use uiua::{
+    Assembly, BindingKind, Compiler, Diagnostic, Node, Span, TraceFrame, Uiua, UiuaError, Value,
+};
 use wasm_bindgen::{prelude::wasm_bindgen, JsError, JsValue};
 
 use crate::{
-    backend::{self, CustomBackend, ExternalBackendHandlers},
-    formatting::DocumentSpan, value::NativeValueWrapper,
+    backend::{self, format_error, CustomBackend, ExternalBackendHandlers},
+    bindings::{
+        binding_name, host_binding_index, top_level_bindings, validate_host_name, BindingDocs,
+        HostBindingInfo, HostNamespace, UiuaBindingKind, HOST_SOURCE_PATH,
+    },
+    formatting::DocumentSpan,
+    modules::ModuleLoader,
+    value::NativeValueWrapper,
 };
 
 #[wasm_bindgen]
 pub struct UiuaRuntimeInternal {
     bindings: Vec<JsBinding>,
+    constants: Vec<(String, Value)>,
     compiler: Option<CompilerRef>,
     backend: ExternalBackendHandlers,
     execution_limit_seconds: Option<f64>,
+    trace_limit: Option<usize>,
+    random_seed: Option<u64>,
+    modules: ModuleLoader,
 }
 
 #[wasm_bindgen]
@@ -23,16 +39,116 @@ impl UiuaRuntimeInternal {
     pub fn new() -> Self {
         UiuaRuntimeInternal {
             bindings: Vec::new(),
+            constants: Vec::new(),
             compiler: None,
             backend: ExternalBackendHandlers::default(),
             execution_limit_seconds: None,
+            trace_limit: None,
+            random_seed: None,
+            modules: ModuleLoader::default(),
         }
     }
 
+    /// Register a JavaScript function that can be called by name from Uiua code.
+    ///
+    /// The optional `docs` is an object with `doc`, `parameters` and `example` fields, which
+    /// become the doc comment of the binding.
     #[wasm_bindgen(js_name = addBinding)]
-    pub fn add_binding(&mut self, name: String, inputs: usize, outputs: usize, callback: Function) {
-        let binding = JsBinding::new(name, inputs, outputs, callback);
+    pub fn add_binding(
+        &mut self,
+        name: String,
+        inputs: usize,
+        outputs: usize,
+        callback: Function,
+        docs: JsValue,
+    ) -> Result<(), JsValue> {
+        let docs: Option<BindingDocs> = serde_wasm_bindgen::from_value(docs)?;
+        let generated = name.contains('~') || docs.is_some();
+        validate_host_name(&name, generated).map_err(|err| JsError::new(&err))?;
+        self.check_unregistered(&name)?;
+        let binding = JsBinding::new(name, inputs, outputs, callback, docs);
         self.bindings.push(binding);
+        Ok(())
+    }
+
+    /// List the functions and constants registered by the host.
+    #[wasm_bindgen(js_name = listHostBindings)]
+    pub fn list_host_bindings(&self) -> JsValue {
+        let functions = self.bindings.iter().map(|binding| {
+            HostBindingInfo::new(
+                binding.name.clone(),
+                UiuaBindingKind::Function,
+                binding.signature,
+                binding.docs.clone().unwrap_or_default(),
+            )
+        });
+
+        let constants = self.constants.iter().map(|(name, _)| {
+            HostBindingInfo::new(
+                name.clone(),
+                UiuaBindingKind::Constant,
+                (0, 1),
+                BindingDocs::default(),
+            )
+        });
+
+        let bindings = functions.chain(constants).collect::<Vec<HostBindingInfo>>();
+        serde_wasm_bindgen::to_value(&bindings).unwrap()
+    }
+
+    /// Register a constant value that can be referred to by name from Uiua code.
+    /// Use `~` in the name to place it in a module, like `Host~Version`.
+    #[wasm_bindgen(js_name = addConstant)]
+    pub fn add_constant(
+        &mut self,
+        name: String,
+        value: &NativeValueWrapper,
+    ) -> Result<(), JsError> {
+        validate_host_name(&name, true).map_err(|err| JsError::new(&err))?;
+        self.check_unregistered(&name)?;
+        self.constants.push((name, value.to_value()));
+        Ok(())
+    }
+
+    fn check_unregistered(&self, name: &str) -> Result<(), JsError> {
+        let functions = self.bindings.iter().map(|binding| &binding.name);
+        let constants = self.constants.iter().map(|(name, _)| name);
+        if functions
+            .chain(constants)
+            .any(|registered| registered == name)
+        {
+            return Err(JsError::new(&format!(
+                "A binding named {name} was already added"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Register the source of a module that can be imported from Uiua code by its path.
+    #[wasm_bindgen(js_name = addModule)]
+    pub fn add_module(&mut self, path: String, source: String) {
+        self.modules.add_file(&path, source);
+    }
+
+    /// Add the files of a git package to the package cache, so it can be imported by its URL.
+    /// The version is the branch or commit the package is imported with, if any.
+    #[wasm_bindgen(js_name = addPackage)]
+    pub fn add_package(
+        &mut self,
+        url: String,
+        version: Option<String>,
+        files: JsValue,
+    ) -> Result<(), JsValue> {
+        let files: HashMap<String, String> = serde_wasm_bindgen::from_value(files)?;
+        self.modules
+            .add_package(&url, version.as_deref(), files)
+            .map_err(|err| JsError::new(&err).into())
+    }
+
+    /// Set a callback that returns the source of an imported module, or `undefined` if it doesn't exist.
+    #[wasm_bindgen(js_name = setModuleResolver)]
+    pub fn set_module_resolver(&mut self, resolver: Function) {
+        self.modules.set_resolver(JsFunctionWrapper(resolver));
     }
 
     #[wasm_bindgen(js_name = setCompiler)]
@@ -55,25 +171,294 @@ impl UiuaRuntimeInternal {
         self.execution_limit_seconds = Some(seconds);
     }
 
+    /// Seed the random number generator before each run so that the results are reproducible.
+    #[wasm_bindgen(js_name = setRandomSeed)]
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random_seed = Some(seed);
+    }
+
+    /// Record the stack after each top level instruction, keeping at most `limit` entries.
+    #[wasm_bindgen(js_name = setTraceLimit)]
+    pub fn set_trace_limit(&mut self, limit: usize) {
+        self.trace_limit = Some(limit);
+    }
+
     fn build_uiua_backend(&self) -> CustomBackend {
         let mut backend = CustomBackend::new();
         backend.set_backend(self.backend.clone());
+        backend.set_modules(self.modules.clone());
         backend
     }
+
+    pub(crate) fn build_interpreter(&self) -> Result<Uiua, JsValue> {
+        let mut uiua = Uiua::with_safe_sys();
+
+        if let Some(seconds) = self.execution_limit_seconds {
+            // The limit is checked against the backend's clock, which a virtual clock would freeze
+            if self.backend.uses_virtual_time() {
+                return Err(JsError::new(
+                    "An execution limit can't be used together with virtual time",
+                )
+                .into());
+            }
+
+            uiua = uiua.with_execution_limit(Duration::from_secs_f64(seconds));
+        }
+
+        if let Some(seed) = self.random_seed {
+            uiua::seed_random(seed);
+        }
+
+        Ok(uiua)
+    }
+
+    pub(crate) fn build_compiler(&self) -> Result<Compiler, JsValue> {
+        let backend = self.build_uiua_backend();
+
+        let mut compiler: Compiler = match self.compiler.as_ref() {
+            Some(compiler) => {
+                let mut compiler = compiler.compiler.clone();
+                compiler.set_backend(backend);
+                compiler
+            }
+            None => Compiler::with_backend(backend),
+        };
+
+        self.bind_host_values(&mut compiler)?;
+
+        // This line makes sure that if the compiler was used before, it won't rerun the previous code
+        compiler.assembly_mut().root.clear();
+
+        Ok(compiler)
+    }
+
+    fn bind_host_values(&self, compiler: &mut Compiler) -> Result<(), JsValue> {
+        let mut namespace = HostNamespace::default();
+
+        // Namespaced and documented functions are defined through generated code
+        let functions = self
+            .bindings
+            .iter()
+            .map(|binding| {
+                let path = binding.name.split('~').collect::<Vec<&str>>();
+                if path.len() > 1 || binding.docs.is_some() {
+                    (
+                        namespace.insert_function(&path, binding.docs.clone()),
+                        binding,
+                        true,
+                    )
+                } else {
+                    (binding.name.clone(), binding, false)
+                }
+            })
+            .collect::<Vec<(String, &JsBinding, bool)>>();
+
+        self.constants.iter().for_each(|(name, _)| {
+            let path = name.split('~').collect::<Vec<&str>>();
+            namespace.insert_constant(&path);
+        });
+
+        // A compiler that was used before already has the namespace, unless the bindings changed
+        let source = namespace.to_source();
+        let loaded = compiler
+            .assembly()
+            .inputs
+            .files
+            .get(Path::new(HOST_SOURCE_PATH))
+            .map(|loaded| loaded.value().as_str() == source)
+            .unwrap_or(false);
+        let load_namespace = !namespace.is_empty() && !loaded;
+
+        for (name, binding, namespaced) in functions {
+            if namespaced && !load_namespace {
+                continue;
+            }
+
+            let callback = binding.callback.clone();
+            let binding_name = binding.name.clone();
+            let (inputs, outputs) = binding.signature;
+            let _ = compiler.create_bind_function(&name, binding.signature, move |uiua| {
+                let height_before = uiua.stack().len() as isize;
+                let wrapped = UiuaRef::new(uiua);
+                if let Err(error) = callback.call1(&JsValue::undefined(), &JsValue::from(wrapped)) {
+                    return Err(uiua.error(format_error(error)));
+                }
+
+                // A callback that doesn't match its signature would silently corrupt the stack
+                let expected = outputs as isize - inputs as isize;
+                let actual = uiua.stack().len() as isize - height_before;
+                if actual != expected {
+                    return Err(uiua.error(format!(
+                        "Binding {binding_name} has signature |{inputs}.{outputs}, so it should \
+                        change the stack size by {expected}, but it changed it by {actual}"
+                    )));
+                }
+
+                Ok(())
+            });
+        }
+
+        if load_namespace {
+            compiler
+                .load_str_src(&source, Path::new(HOST_SOURCE_PATH))
+                .map_err(to_js_error)?;
+        }
+
+        // The constants are compiled with placeholders, so their values are set every time
+        for (name, constant) in &self.constants {
+            let path = name.split('~').collect::<Vec<&str>>();
+            let index = match host_binding_index(compiler.assembly(), &path) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let bindings = compiler.assembly_mut().bindings.make_mut();
+            if let BindingKind::Const(value) = &mut bindings[index].kind {
+                *value = Some(constant.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a function defined in the compiled code, which can then be called any number of times.
+    /// The code is not executed again, and the function keeps its own interpreter between calls.
+    /// Values can't hold functions in this version of Uiua, so only named functions can be used.
+    #[wasm_bindgen(js_name = getFunction)]
+    pub fn get_function(
+        &self,
+        compiler: &CompilerRef,
+        name: String,
+    ) -> Result<UiuaFunctionInternal, JsValue> {
+        let function = match compiler.function(&name) {
+            Some(function) => function,
+            None => return Err(JsError::new(&format!("{name} is not a function")).into()),
+        };
+
+        let mut uiua = self.build_interpreter()?;
+        let mut compiler = compiler.compiler.clone();
+        compiler.set_backend(self.build_uiua_backend());
+        compiler.assembly_mut().root.clear();
+
+        // Load the assembly into the interpreter without running anything
+        uiua.run_compiler(&mut compiler).map_err(to_js_error)?;
+
+        Ok(UiuaFunctionInternal {
+            uiua,
+            function,
+            name,
+        })
+    }
+
+    /// Call a function defined in the compiled code with the given arguments.
+    ///
+    /// The arguments are given in the order of the function's parameters, so the first one ends
+    /// up on top of the stack. The outputs are returned in the same order.
+    #[wasm_bindgen(js_name = callFunction)]
+    pub fn call_function(
+        &self,
+        compiler: &CompilerRef,
+        name: String,
+        args: Vec<NativeValueWrapper>,
+    ) -> Result<Vec<NativeValueWrapper>, JsValue> {
+        self.get_function(compiler, name)?.call(args)
+    }
+
+    /// Rebuild a compiler from a snapshot created with `CompilerRef::serialize`.
+    ///
+    /// The code of the snapshot is compiled again with the bindings of this runtime, but
+    /// nothing is executed; the values of constants are taken from the snapshot instead.
+    #[wasm_bindgen(js_name = restoreCompiler)]
+    pub fn restore_compiler(&self, bytes: Vec<u8>) -> Result<CompilerRef, JsValue> {
+        let snapshot: CompilerSnapshot = serde_json::from_slice(&bytes)
+            .map_err(|err| JsError::new(&format!("Invalid compiler snapshot: {err}")))?;
+
+        if snapshot.version != COMPILER_SNAPSHOT_VERSION {
+            return Err(JsError::new(&format!(
+                "Unsupported compiler snapshot version {}",
+                snapshot.version
+            ))
+            .into());
+        }
+
+        let saved = Assembly::from_uasm(&snapshot.assembly)
+            .map_err(|err| JsError::new(&format!("Invalid compiler snapshot: {err}")))?;
+
+        // Imported files are taken from the snapshot, so they don't have to be registered again
+        let mut modules = self.modules.clone();
+        for (path, source) in &snapshot.modules {
+            modules.add_restored_file(path, source.clone());
+        }
+
+        let mut backend = self.build_uiua_backend();
+        backend.set_modules(modules);
+
+        let mut compiler = Compiler::with_backend(backend);
+        for source in &snapshot.sources {
+            self.bind_host_values(&mut compiler)?;
+            compiler.load_str(source).map_err(to_js_error)?;
+            compiler.assembly_mut().root.clear();
+        }
+
+        let asm = compiler.assembly();
+        if asm.bindings.len() != saved.bindings.len() {
+            return Err(JsError::new(&format!(
+                "The compiler snapshot has {} bindings, but restoring it created {}. \
+                The host bindings and constants have to be the same as when it was saved",
+                saved.bindings.len(),
+                asm.bindings.len()
+            ))
+            .into());
+        }
+
+        for (binding, saved_binding) in asm.bindings.iter().zip(saved.bindings.iter()) {
+            let name = binding_name(asm, binding);
+            let saved_name = binding_name(&saved, saved_binding);
+            if name != saved_name {
+                return Err(JsError::new(&format!(
+                    "The compiler snapshot has a binding {saved_name} where restoring it created \
+                    {name}. The host bindings and constants have to be the same as when it was saved"
+                ))
+                .into());
+            }
+        }
+
+        let bindings = compiler.assembly_mut().bindings.make_mut();
+        for (binding, saved) in bindings.iter_mut().zip(saved.bindings.iter()) {
+            if let (BindingKind::Const(value), BindingKind::Const(Some(saved))) =
+                (&mut binding.kind, &saved.kind)
+            {
+                *value = Some(saved.clone());
+            }
+        }
+
+        Ok(CompilerRef {
+            compiler,
+            sources: snapshot.sources,
+        })
+    }
 }
 
 pub struct JsBinding {
     name: String,
     signature: (usize, usize),
     callback: JsFunctionWrapper,
+    docs: Option<BindingDocs>,
 }
 
 impl JsBinding {
-    pub fn new(name: String, inputs: usize, outputs: usize, callback: Function) -> JsBinding {
+    pub fn new(
+        name: String,
+        inputs: usize,
+        outputs: usize,
+        callback: Function,
+        docs: Option<BindingDocs>,
+    ) -> JsBinding {
         JsBinding {
             name,
             signature: (inputs, outputs),
             callback: JsFunctionWrapper(callback),
+            docs,
         }
     }
 }
@@ -84,11 +469,77 @@ unsafe impl Send for JsFunctionWrapper {}
 unsafe impl Sync for JsFunctionWrapper {}
 
 impl JsFunctionWrapper {
+    pub fn call0(&self, this: &JsValue) -> Result<JsValue, JsValue> {
+        self.0.call0(this)
+    }
+
     pub fn call1(&self, this: &JsValue, arg: &JsValue) -> Result<JsValue, JsValue> {
         self.0.call1(this, arg)
     }
 }
 
+/// A Uiua function together with the interpreter it runs in.
+#[wasm_bindgen]
+pub struct UiuaFunctionInternal {
+    uiua: Uiua,
+    function: uiua::Function,
+    name: String,
+}
+
+#[wasm_bindgen]
+impl UiuaFunctionInternal {
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn inputs(&self) -> usize {
+        self.function.sig.args
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn outputs(&self) -> usize {
+        self.function.sig.outputs
+    }
+
+    /// Call the function, with the first argument on top of the stack.
+    /// The outputs are returned with the top of the stack first.
+    pub fn call(
+        &mut self,
+        args: Vec<NativeValueWrapper>,
+    ) -> Result<Vec<NativeValueWrapper>, JsValue> {
+        if args.len() != self.function.sig.args {
+            return Err(JsError::new(&format!(
+                "{} expects {} arguments, but {} were given",
+                self.name,
+                self.function.sig.args,
+                args.len()
+            ))
+            .into());
+        }
+
+        // Anything left over from a previous failed call shouldn't leak into this one
+        self.uiua.take_stack();
+
+        args.into_iter().rev().for_each(|value| {
+            self.uiua.push(value.to_value());
+        });
+
+        self.uiua.call(&self.function).map_err(to_js_error)?;
+
+        let outputs = self
+            .uiua
+            .take_stack()
+            .into_iter()
+            .rev()
+            .map(NativeValueWrapper::new)
+            .collect();
+
+        Ok(outputs)
+    }
+}
+
 #[wasm_bindgen]
 pub struct UiuaRef {
     uiua: *mut Uiua,
@@ -114,21 +565,184 @@ impl UiuaRef {
         uiua.push(value.to_value());
         Ok(())
     }
+
+    /// Get the value `depth` values below the top of the stack without popping it.
+    pub fn peek(&self, depth: usize) -> Result<NativeValueWrapper, JsValue> {
+        let uiua = unsafe { &*self.uiua };
+        let stack = uiua.stack();
+        match stack.len().checked_sub(depth + 1) {
+            Some(index) => Ok(NativeValueWrapper::new(stack[index].clone())),
+            None => Err(to_js_error(uiua.error(format!(
+                "Cannot peek at depth {depth}, the stack only has {} values",
+                stack.len()
+            )))),
+        }
+    }
+
+    #[wasm_bindgen(js_name = stackSize)]
+    pub fn stack_size(&self) -> usize {
+        let uiua = unsafe { &*self.uiua };
+        uiua.stack().len()
+    }
+
+    /// Pop `count` values, with the top of the stack first.
+    #[wasm_bindgen(js_name = popMany)]
+    pub fn pop_many(&mut self, count: usize) -> Result<Vec<NativeValueWrapper>, JsValue> {
+        (0..count).map(|_| self.pop()).collect()
+    }
+
+    #[wasm_bindgen(js_name = popNumber)]
+    pub fn pop_number(&mut self) -> Result<f64, JsValue> {
+        let uiua = unsafe { &mut *self.uiua };
+        let result = uiua
+            .pop(())
+            .and_then(|value| value.as_num(uiua, "Expected a number"));
+        result.map_err(to_js_error)
+    }
+
+    #[wasm_bindgen(js_name = popString)]
+    pub fn pop_string(&mut self) -> Result<String, JsValue> {
+        let uiua = unsafe { &mut *self.uiua };
+        let result = uiua
+            .pop(())
+            .and_then(|value| value.as_string(uiua, "Expected a string"));
+        result.map_err(to_js_error)
+    }
+
+    #[wasm_bindgen(js_name = popBytes)]
+    pub fn pop_bytes(&mut self) -> Result<Vec<u8>, JsValue> {
+        let uiua = unsafe { &mut *self.uiua };
+        let result = uiua
+            .pop(())
+            .and_then(|value| value.as_bytes(uiua, "Expected a list of bytes"));
+        result.map_err(to_js_error)
+    }
+
+    #[wasm_bindgen(js_name = pushNumber)]
+    pub fn push_number(&mut self, number: f64) {
+        let uiua = unsafe { &mut *self.uiua };
+        uiua.push(number);
+    }
+
+    #[wasm_bindgen(js_name = pushString)]
+    pub fn push_string(&mut self, string: String) {
+        let uiua = unsafe { &mut *self.uiua };
+        uiua.push(string);
+    }
+
+    #[wasm_bindgen(js_name = pushBytes)]
+    pub fn push_bytes(&mut self, bytes: Vec<u8>) {
+        let uiua = unsafe { &mut *self.uiua };
+        uiua.push(bytes);
+    }
+
+    /// The span of the code that called the binding.
+    #[wasm_bindgen(getter)]
+    pub fn span(&self) -> JsValue {
+        let uiua = unsafe { &*self.uiua };
+        serde_wasm_bindgen::to_value(&DocumentSpan::from(uiua.span())).unwrap()
+    }
+
+    /// Always throws, making the binding fail with a Uiua error at the call site.
+    #[wasm_bindgen(js_name = throwError)]
+    pub fn throw_error(&self, message: String) -> Result<(), JsError> {
+        Err(JsError::new(&message))
+    }
 }
 
 #[wasm_bindgen]
 pub struct UiuaExecutionResultInternal {
     stack: Vec<Value>,
-    compiler: Compiler,
+    compiler: CompilerRef,
     stdout: Vec<u8>,
     stderr: Vec<u8>,
     diagnostics: Vec<Diagnostic>,
+    trace: Vec<TraceEntry>,
+}
+
+struct TraceEntry {
+    span: Option<Span>,
+    stack: Vec<Value>,
+}
+
+impl TraceEntry {
+    fn to_js(&self) -> JsValue {
+        let object = Object::new().into();
+        let span = match &self.span {
+            Some(span) => serde_wasm_bindgen::to_value(&DocumentSpan::from(span.clone())).unwrap(),
+            None => JsValue::undefined(),
+        };
+        let stack = self
+            .stack
+            .iter()
+            .map(|value| JsValue::from(NativeValueWrapper::new(value.clone())))
+            .collect::<Array>();
+
+        Reflect::set(&object, &JsValue::from("span"), &span).unwrap();
+        Reflect::set(&object, &JsValue::from("stack"), &stack).unwrap();
+        object
+    }
 }
 
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct CompilerRef {
-    compiler: Compiler,
+    pub(crate) compiler: Compiler,
+    sources: Vec<String>,
+}
+
+const COMPILER_SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CompilerSnapshot {
+    version: u32,
+    sources: Vec<String>,
+    /// The sources of imported files and packages by their path.
+    modules: BTreeMap<String, String>,
+    assembly: String,
+}
+
+#[wasm_bindgen]
+impl CompilerRef {
+    /// Serialize the compiled code and the values of its bindings, to be restored with
+    /// `UiuaRuntimeInternal::restore_compiler`.
+    pub fn serialize(&self) -> Vec<u8> {
+        let snapshot = CompilerSnapshot {
+            version: COMPILER_SNAPSHOT_VERSION,
+            sources: self.sources.clone(),
+            modules: self.modules(),
+            assembly: self.compiler.assembly().to_uasm(),
+        };
+
+        serde_json::to_vec(&snapshot).unwrap()
+    }
+}
+
+impl CompilerRef {
+    pub(crate) fn function(&self, name: &str) -> Option<uiua::Function> {
+        top_level_bindings(self.compiler.assembly())
+            .into_iter()
+            .find(|(binding_name, _)| binding_name == name)
+            .and_then(|(_, binding)| match &binding.kind {
+                BindingKind::Func(function) => Some(function.clone()),
+                _ => None,
+            })
+    }
+
+    /// The files that were loaded by imports, including the files of git packages.
+    fn modules(&self) -> BTreeMap<String, String> {
+        self.compiler
+            .assembly()
+            .inputs
+            .files
+            .iter()
+            .filter(|entry| entry.key().as_path() != Path::new(HOST_SOURCE_PATH))
+            .map(|entry| {
+                let path = entry.key().to_string_lossy().into_owned();
+                (path, entry.value().to_string())
+            })
+            .collect()
+    }
 }
 
 #[wasm_bindgen]
@@ -146,9 +760,7 @@ impl UiuaExecutionResultInternal {
 
     #[wasm_bindgen(getter)]
     pub fn compiler(&self) -> CompilerRef {
-        CompilerRef {
-            compiler: self.compiler.clone(),
-        }
+        self.compiler.clone()
     }
 
     #[wasm_bindgen(getter)]
@@ -171,6 +783,15 @@ impl UiuaExecutionResultInternal {
 
         serde_wasm_bindgen::to_value(&diagnostics).unwrap()
     }
+
+    #[wasm_bindgen(getter)]
+    pub fn trace(&self) -> JsValue {
+        self.trace
+            .iter()
+            .map(TraceEntry::to_js)
+            .collect::<Array>()
+            .into()
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -208,7 +829,7 @@ impl From<Diagnostic> for UiuaDiagnostic {
 }
 
 #[derive(serde::Serialize)]
-struct UiuaTraceFrame {
+pub(crate) struct UiuaTraceFrame {
     pub line: String,
     pub span: DocumentSpan,
 }
@@ -248,6 +869,44 @@ impl From<UiuaError> for SimplifiedUiuaError {
     }
 }
 
+/// Splits a node into the instructions that are executed one after another.
+pub(crate) fn top_level_nodes(node: Node) -> Vec<Node> {
+    match node {
+        Node::Run(nodes) => nodes.into_iter().collect(),
+        node => vec![node],
+    }
+}
+
+/// Runs the compiled code one top level instruction at a time, recording the stack after each.
+/// Values share their data between snapshots, so unchanged values are not copied.
+fn run_traced(
+    uiua: &mut Uiua,
+    compiler: &mut Compiler,
+    limit: usize,
+) -> Result<Vec<TraceEntry>, UiuaError> {
+    let nodes = top_level_nodes(compiler.assembly().root.clone());
+    let mut trace = Vec::new();
+
+    for node in nodes {
+        let span = node
+            .span()
+            .and_then(|span| compiler.assembly().spans.get(span).cloned());
+
+        compiler.assembly_mut().root = node;
+        uiua.run_compiler(compiler)?;
+
+        if trace.len() < limit {
+            trace.push(TraceEntry {
+                span,
+                stack: uiua.stack().to_vec(),
+            });
+        }
+    }
+
+    compiler.assembly_mut().root.clear();
+    Ok(trace)
+}
+
 pub fn to_js_error(err: UiuaError) -> JsValue {
     serde_wasm_bindgen::to_value(&SimplifiedUiuaError::from(err))
         .unwrap()
@@ -258,38 +917,10 @@ pub fn to_js_error(err: UiuaError) -> JsValue {
 pub fn run_code(
     code: String,
     initial_values: Vec<NativeValueWrapper>,
-    runtime: UiuaRuntimeInternal,
+    runtime: &UiuaRuntimeInternal,
 ) -> Result<UiuaExecutionResultInternal, JsValue> {
-    let mut uiua = Uiua::with_safe_sys();
-
-    if let Some(seconds) = runtime.execution_limit_seconds.clone() {
-        uiua = uiua.with_execution_limit(Duration::from_secs_f64(seconds));
-    }
-
-    let backend = runtime.build_uiua_backend();
-
-    let mut compiler: Compiler = match runtime.compiler.as_ref() {
-        Some(compiler) => {
-            let mut compiler = compiler.compiler.clone();
-            compiler.set_backend(backend);
-            compiler
-        }
-        None => Compiler::with_backend(backend),
-    };
-
-    runtime.bindings.into_iter().for_each(|binding| {
-        let callback = binding.callback;
-        let _ = compiler.create_bind_function(&binding.name, binding.signature, move |uiua| {
-            let wrapped = UiuaRef::new(uiua);
-            callback
-                .call1(&JsValue::undefined(), &JsValue::from(wrapped))
-                .unwrap();
-            Ok(())
-        });
-    });
-
-    // This line makes sure that if the compiler was used before, it won't rerun the previous code
-    compiler.assembly_mut().root.clear();
+    let mut uiua = runtime.build_interpreter()?;
+    let mut compiler = runtime.build_compiler()?;
 
     // Load the code into the compiler
     let result = compiler.load_str(code.as_str());
@@ -302,21 +933,69 @@ pub fn run_code(
         uiua.push(value.to_value());
     });
 
-    let result = uiua.run_compiler(&mut compiler);
+    let trace = match runtime.trace_limit {
+        Some(limit) => run_traced(&mut uiua, &mut compiler, limit),
+        None => uiua.run_compiler(&mut compiler).map(|_| Vec::new()),
+    };
 
-    if let Err(err) = result {
-        return Err(to_js_error(err));
-    }
+    let trace = match trace {
+        Ok(trace) => trace,
+        Err(err) => return Err(to_js_error(err)),
+    };
 
     let diagnostics: Vec<Diagnostic> = compiler.take_diagnostics().into_iter().collect();
+    let mut sources = match &runtime.compiler {
+        Some(compiler) => compiler.sources.clone(),
+        None => Vec::new(),
+    };
+    sources.push(code);
+
     let backend = uiua.downcast_backend::<backend::CustomBackend>().unwrap();
     let result = UiuaExecutionResultInternal {
         stack: uiua.stack().to_vec(),
-        compiler,
+        compiler: CompilerRef { compiler, sources },
         stdout: backend.stdout(),
         stderr: backend.stderr(),
         diagnostics,
+        trace,
     };
 
     Ok(result)
 }
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use js_sys::{Array, Function, Map, Reflect};
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::UiuaRuntimeInternal;
+    use crate::bindings::BindingDocs;
+
+    fn property(object: &JsValue, name: &str) -> Option<String> {
+        Reflect::get(object, &JsValue::from(name))
+            .unwrap()
+            .as_string()
+    }
+
+    #[wasm_bindgen_test]
+    fn host_bindings_are_listed_as_plain_objects() {
+        let mut runtime = UiuaRuntimeInternal::new();
+        let docs = BindingDocs {
+            doc: Some("Fetches a URL".to_string()),
+            ..BindingDocs::default()
+        };
+        let docs = serde_wasm_bindgen::to_value(&docs).unwrap();
+        runtime
+            .add_binding("Fetch".to_string(), 1, 1, Function::new_no_args(""), docs)
+            .unwrap();
+
+        let bindings = Array::from(&runtime.list_host_bindings());
+        let info = bindings.get(0);
+
+        assert!(info.is_object());
+        assert!(!info.is_instance_of::<Map>());
+        assert_eq!(property(&info, "name").as_deref(), Some("Fetch"));
+        assert_eq!(property(&info, "doc").as_deref(), Some("Fetches a URL"));
+    }
+}
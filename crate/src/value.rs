@@ -1,10 +1,35 @@
-use js_sys::{Object, Reflect};
-use uiua::{encode::SmartOutput, SafeSys, Value};
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    mem::discriminant,
+};
+
+use js_sys::{Array, ArrayBuffer, Float64Array, Map, Object, Reflect, Uint8Array, JSON};
+use uiua::{encode::SmartOutput, Boxed, SafeSys, Uiua, UiuaResult, Value};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError, JsValue};
+
+use crate::{
+    encoding::{decode_value, encode_value},
+    runtime::to_js_error,
+};
 
 #[wasm_bindgen]
 pub struct NativeValueWrapper(Value);
 
+/// A summary of a value.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValueInfo {
+    #[serde(rename = "type")]
+    type_: String,
+    rank: usize,
+    shape: Vec<usize>,
+    element_count: usize,
+    is_boolean: bool,
+    label: Option<String>,
+    has_map_keys: bool,
+}
+
 impl NativeValueWrapper {
     pub fn new(value: Value) -> Self {
         NativeValueWrapper(value)
@@ -15,12 +40,322 @@ impl NativeValueWrapper {
     }
 }
 
+/// The 64-bit FNV-1a hash. The algorithm of the standard library's `DefaultHasher` may change
+/// between Rust versions, while this one stays the same.
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Combines the keys or values of a map into the rows of one array. Scalars of one type are kept
+/// as they are, anything else is boxed like `°json` does, so a string is boxed whatever its length.
+fn rows_to_value(rows: Vec<Value>, env: &Uiua) -> UiuaResult<Value> {
+    let scalars = rows.iter().all(|row| row.rank() == 0)
+        && rows
+            .windows(2)
+            .all(|pair| discriminant(&pair[0]) == discriminant(&pair[1]));
+
+    if scalars {
+        Value::from_row_values(rows, env)
+    } else {
+        Ok(rows
+            .into_iter()
+            .map(Boxed)
+            .collect::<uiua::Array<Boxed>>()
+            .into())
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+struct ToJsOptions {
+    deep: Option<bool>,
+    complex: Option<ComplexFormat>,
+    chars: Option<CharFormat>,
+    maps: Option<MapFormat>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default, PartialEq)]
+enum ComplexFormat {
+    /// `[re, im]`
+    #[default]
+    #[serde(rename = "pair")]
+    Pair,
+    /// `{ re, im }`
+    #[serde(rename = "object")]
+    Object,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default, PartialEq)]
+enum CharFormat {
+    /// The last axis of a character array becomes a string
+    #[default]
+    #[serde(rename = "string")]
+    String,
+    /// Every character becomes a string of its own
+    #[serde(rename = "char")]
+    Char,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default, PartialEq)]
+enum MapFormat {
+    /// A plain object, keys that are not strings or numbers are shown as Uiua code
+    #[default]
+    #[serde(rename = "object")]
+    Object,
+    /// A JS `Map`
+    #[serde(rename = "map")]
+    Map,
+}
+
+fn value_to_js(value: &Value, options: &ToJsOptions) -> JsValue {
+    if let Some(keys) = &value.meta().map_keys {
+        return map_to_js(keys.clone().normalized(), value, options);
+    }
+
+    let mut shape = value.shape().to_vec();
+    let elements: Vec<JsValue> = match value {
+        Value::Num(array) => array.data.iter().map(|&n| JsValue::from(n)).collect(),
+        Value::Byte(array) => array.data.iter().map(|&b| JsValue::from(b)).collect(),
+        Value::Complex(array) => array
+            .data
+            .iter()
+            .map(|c| match options.complex.unwrap_or_default() {
+                ComplexFormat::Pair => {
+                    Array::of2(&JsValue::from(c.re), &JsValue::from(c.im)).into()
+                }
+                ComplexFormat::Object => {
+                    let object = Object::new().into();
+                    Reflect::set(&object, &JsValue::from("re"), &JsValue::from(c.re)).unwrap();
+                    Reflect::set(&object, &JsValue::from("im"), &JsValue::from(c.im)).unwrap();
+                    object
+                }
+            })
+            .collect(),
+        Value::Char(array) => match (options.chars.unwrap_or_default(), shape.pop()) {
+            (CharFormat::String, Some(length)) if length > 0 => array
+                .data
+                .chunks(length)
+                .map(|chunk| JsValue::from(chunk.iter().collect::<String>()))
+                .collect(),
+            (CharFormat::String, Some(_)) => {
+                let count = shape.iter().product();
+                vec![JsValue::from(""); count]
+            }
+            (_, last) => {
+                shape.extend(last);
+                array
+                    .data
+                    .iter()
+                    .map(|c| JsValue::from(c.to_string()))
+                    .collect()
+            }
+        },
+        Value::Box(array) => array
+            .data
+            .iter()
+            .map(|boxed| match options.deep {
+                Some(true) => value_to_js(&boxed.0, options),
+                _ => JsValue::from(NativeValueWrapper(boxed.0.clone())),
+            })
+            .collect(),
+    };
+
+    nest_elements(&mut elements.into_iter(), &shape)
+}
+
+fn map_to_js(keys: Value, values: &Value, options: &ToJsOptions) -> JsValue {
+    let convert_row = |row: Value| match (&row, options.deep) {
+        (Value::Box(_), Some(true)) => value_to_js(&row.unboxed(), options),
+        _ => value_to_js(&row, options),
+    };
+
+    let entries = keys.rows().zip(values.rows());
+    match options.maps.unwrap_or_default() {
+        MapFormat::Map => {
+            let map = Map::new();
+            for (key, value) in entries {
+                map.set(&convert_row(key), &convert_row(value));
+            }
+            map.into()
+        }
+        MapFormat::Object => {
+            let object = Object::new().into();
+            for (key, value) in entries {
+                let key = js_primitive(&key).unwrap_or_else(|| JsValue::from(key.show()));
+                Reflect::set(&object, &key, &convert_row(value)).unwrap();
+            }
+            object
+        }
+    }
+}
+
+/// Builds nested arrays of the given shape out of the elements in row-major order.
+fn nest_elements(elements: &mut impl Iterator<Item = JsValue>, shape: &[usize]) -> JsValue {
+    match shape.split_first() {
+        None => elements.next().unwrap_or(JsValue::undefined()),
+        Some((&length, rest)) => (0..length)
+            .map(|_| nest_elements(elements, rest))
+            .collect::<Array>()
+            .into(),
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+struct CsvOptions {
+    delimiter: Option<char>,
+}
+
+impl CsvOptions {
+    fn delimiter(&self) -> Result<u8, String> {
+        match self.delimiter.unwrap_or(',') {
+            delimiter if delimiter.is_ascii() => Ok(delimiter as u8),
+            delimiter => Err(format!("The delimiter {delimiter:?} is not ASCII")),
+        }
+    }
+}
+
+/// Parses CSV with the given options into a rank 2 array of boxed strings, padding short
+/// rows with empty strings like `°csv` does.
+fn csv_to_value(text: &str, options: &CsvOptions) -> Result<Value, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter()?)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let records = reader
+        .records()
+        .collect::<Result<Vec<csv::StringRecord>, csv::Error>>()
+        .map_err(|err| err.to_string())?;
+
+    let columns = records.iter().map(|record| record.len()).max().unwrap_or(0);
+    let cells = records
+        .iter()
+        .flat_map(|record| (0..columns).map(move |i| record.get(i).unwrap_or_default()))
+        .map(|cell| Boxed(Value::from(cell.to_string())))
+        .collect::<Vec<Boxed>>();
+
+    Ok(uiua::Array::new([records.len(), columns], cells).into())
+}
+
+/// Writes a value as CSV with the given options. Lists become a single column and rank 2
+/// arrays become one record per row, where strings count as single cells.
+fn value_to_csv(value: &Value, options: &CsvOptions) -> Result<String, String> {
+    let cell_rank = match value {
+        Value::Char(_) => 1,
+        _ => 0,
+    };
+
+    let records = match value.rank().saturating_sub(cell_rank) {
+        0 => vec![vec![csv_cell(value)]],
+        1 => value.rows().map(|row| vec![csv_cell(&row)]).collect(),
+        2 => value
+            .rows()
+            .map(|row| row.rows().map(|cell| csv_cell(&cell)).collect())
+            .collect(),
+        _ => {
+            return Err(format!(
+                "Only lists and tables can be encoded as CSV, but the value has shape {:?}",
+                value.shape().to_vec()
+            ))
+        }
+    };
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter()?)
+        .from_writer(Vec::new());
+
+    for record in records {
+        writer
+            .write_record(record.iter().map(String::as_str))
+            .map_err(|err| err.to_string())?;
+    }
+
+    let bytes = writer.into_inner().map_err(|err| err.to_string())?;
+    String::from_utf8(bytes).map_err(|err| err.to_string())
+}
+
+/// The text of a cell. Numbers are written without Uiua's formatting, like `-1` instead of `¯1`,
+/// so that other programs can read them.
+fn csv_cell(value: &Value) -> String {
+    match value.unboxed() {
+        Value::Char(array) if array.rank() <= 1 => array.elements().collect(),
+        Value::Num(array) if array.rank() == 0 => array.data[0].to_string(),
+        Value::Byte(array) if array.rank() == 0 => array.data[0].to_string(),
+        value => value.show(),
+    }
+}
+
+/// Runs Uiua code on the given arguments in a fresh interpreter, returning the top of the stack.
+/// Used to get the exact semantics of a primitive, like the encoding of `json`.
+fn run_on_values(code: &str, args: Vec<Value>) -> UiuaResult<Value> {
+    let mut env = Uiua::with_safe_sys();
+    args.into_iter().for_each(|arg| env.push(arg));
+    env.run_str(code)?;
+    env.pop(())
+}
+
+pub(crate) fn build_map(keys: Vec<Value>, values: Vec<Value>, env: &Uiua) -> UiuaResult<Value> {
+    let keys = rows_to_value(keys, env)?;
+    let mut values = rows_to_value(values, env)?;
+    values.map(keys, env)?;
+    Ok(values)
+}
+
+/// Converts a string or scalar number, which may be boxed, to its JS equivalent.
+fn js_primitive(value: &Value) -> Option<JsValue> {
+    let value = match value {
+        Value::Box(array) if array.rank() == 0 => &array.data[0].0,
+        value => value,
+    };
+
+    match value {
+        Value::Char(array) if array.rank() == 1 => {
+            Some(JsValue::from(array.elements().collect::<String>()))
+        }
+        Value::Num(array) if array.rank() == 0 => Some(JsValue::from(array.data[0])),
+        Value::Byte(array) if array.rank() == 0 => Some(JsValue::from(array.data[0])),
+        _ => None,
+    }
+}
+
+/// Converts strings and scalar numbers to their JS equivalents, and wraps anything else.
+fn to_js_primitive(value: Value) -> JsValue {
+    match js_primitive(&value) {
+        Some(primitive) => primitive,
+        None => JsValue::from(NativeValueWrapper(value.unboxed())),
+    }
+}
+
 #[wasm_bindgen]
 impl NativeValueWrapper {
     pub fn shape(&self) -> Vec<usize> {
         self.0.shape().to_vec()
     }
 
+    /// A copy of the value, for functions that take ownership of the wrappers they are given.
+    /// The copy shares its data with the original until one of them is modified.
+    #[wasm_bindgen(js_name = clone)]
+    pub fn clone_value(&self) -> NativeValueWrapper {
+        NativeValueWrapper(self.0.clone())
+    }
+
     pub fn label(&self) -> Option<String> {
         self.0.meta().label.as_ref().map(|s| s.to_string())
     }
@@ -33,6 +368,127 @@ impl NativeValueWrapper {
             .map(|keys| NativeValueWrapper(keys.clone().normalized()))
     }
 
+    #[wasm_bindgen(js_name = fromNumber)]
+    pub fn from_number(number: f64) -> NativeValueWrapper {
+        NativeValueWrapper(Value::from(number))
+    }
+
+    #[wasm_bindgen(js_name = fromString)]
+    pub fn from_string(string: String) -> NativeValueWrapper {
+        NativeValueWrapper(Value::from(string))
+    }
+
+    /// Build a map from its keys and values. Keys and values that aren't scalars of one type,
+    /// like strings, are boxed the same way `°json` boxes them.
+    #[wasm_bindgen(js_name = fromMap)]
+    pub fn from_map(
+        keys: Vec<NativeValueWrapper>,
+        values: Vec<NativeValueWrapper>,
+    ) -> Result<NativeValueWrapper, JsValue> {
+        if keys.len() != values.len() {
+            return Err(JsError::new("A map must have the same number of keys and values").into());
+        }
+
+        let keys = keys.into_iter().map(|key| key.0).collect();
+        let values = values.into_iter().map(|value| value.0).collect();
+
+        build_map(keys, values, &Uiua::with_safe_sys())
+            .map(NativeValueWrapper)
+            .map_err(to_js_error)
+    }
+
+    /// Convert a map value into a JS `Map`. Keys and values that are strings or scalar numbers
+    /// become JS strings and numbers, everything else is wrapped.
+    #[wasm_bindgen(js_name = toMap)]
+    pub fn to_map(&self) -> Result<Map, JsError> {
+        let keys = match &self.0.meta().map_keys {
+            Some(keys) => keys.clone().normalized(),
+            None => return Err(JsError::new("The value is not a map")),
+        };
+
+        let map = Map::new();
+        for (key, value) in keys.rows().zip(self.0.rows()) {
+            map.set(&to_js_primitive(key), &to_js_primitive(value));
+        }
+
+        Ok(map)
+    }
+
+    /// Encode the value as JSON the same way the `json` primitive does, parsed into a JS value.
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        let json = run_on_values("json", vec![self.to_value()])
+            .and_then(|json| json.as_string(&Uiua::with_safe_sys(), "Expected JSON text"))
+            .map_err(to_js_error)?;
+
+        JSON::parse(&json)
+    }
+
+    /// Decode JSON text the same way `°json` does.
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(json: String) -> Result<NativeValueWrapper, JsValue> {
+        run_on_values("°json", vec![Value::from(json)])
+            .map(NativeValueWrapper)
+            .map_err(to_js_error)
+    }
+
+    /// Encode the value in a compact binary format that keeps its type, shape, label, map keys
+    /// and the boolean flag Uiua sets on the results of comparisons.
+    pub fn encode(&self) -> Vec<u8> {
+        encode_value(&self.0)
+    }
+
+    /// Decode a value encoded with `encode`.
+    pub fn decode(bytes: Vec<u8>) -> Result<NativeValueWrapper, JsError> {
+        decode_value(&bytes)
+            .map(NativeValueWrapper)
+            .map_err(|err| JsError::new(&err))
+    }
+
+    /// Encode the value into an `ArrayBuffer` of its own, so it can be listed as transferable
+    /// when posting it to a worker instead of being copied again.
+    #[wasm_bindgen(js_name = toTransferable)]
+    pub fn to_transferable(&self) -> ArrayBuffer {
+        Uint8Array::from(encode_value(&self.0).as_slice()).buffer()
+    }
+
+    /// Reconstruct a value from a buffer created with `toTransferable`.
+    #[wasm_bindgen(js_name = fromTransferable)]
+    pub fn from_transferable(buffer: ArrayBuffer) -> Result<NativeValueWrapper, JsError> {
+        Self::decode(Uint8Array::new(&buffer).to_vec())
+    }
+
+    /// Encode a list or table as CSV. `options` is an optional object with a `delimiter`
+    /// field, which defaults to a comma.
+    #[wasm_bindgen(js_name = toCsv)]
+    pub fn to_csv(&self, options: JsValue) -> Result<String, JsValue> {
+        let options: Option<CsvOptions> = serde_wasm_bindgen::from_value(options)?;
+        value_to_csv(&self.0, &options.unwrap_or_default()).map_err(|err| JsError::new(&err).into())
+    }
+
+    /// Parse CSV into a rank 2 array of boxed strings. `options` is an optional object
+    /// with a `delimiter` field, which defaults to a comma.
+    #[wasm_bindgen(js_name = fromCsv)]
+    pub fn from_csv(text: String, options: JsValue) -> Result<NativeValueWrapper, JsValue> {
+        let options: Option<CsvOptions> = serde_wasm_bindgen::from_value(options)?;
+        csv_to_value(&text, &options.unwrap_or_default())
+            .map(NativeValueWrapper)
+            .map_err(|err| JsError::new(&err).into())
+    }
+
+    /// Convert the value into nested JS arrays with the shape of the value applied.
+    ///
+    /// `options` is an optional object with these fields:
+    /// - `deep`: whether to convert the contents of boxes too, instead of wrapping them
+    /// - `complex`: `"pair"` for `[re, im]` or `"object"` for `{ re, im }`
+    /// - `chars`: `"string"` to turn the last axis into strings, or `"char"` for single characters
+    /// - `maps`: `"object"` for plain objects or `"map"` for JS `Map`s
+    #[wasm_bindgen(js_name = toJs)]
+    pub fn to_js(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: Option<ToJsOptions> = serde_wasm_bindgen::from_value(options)?;
+        Ok(value_to_js(&self.0, &options.unwrap_or_default()))
+    }
+
     pub fn data(&self) -> JsValue {
         match self.to_value() {
             Value::Byte(array) => JsValue::from(array.elements().cloned().collect::<Vec<u8>>()),
@@ -60,16 +516,97 @@ impl NativeValueWrapper {
         }
     }
 
+    /// A `Float64Array` over the numbers of the value, directly in wasm memory.
+    ///
+    /// The view is only valid until this wrapper is freed or wasm memory grows, which can happen
+    /// on any call into wasm. Copy the data out if it has to outlive that.
+    #[wasm_bindgen(js_name = viewNumbers)]
+    pub fn view_numbers(&self) -> Result<Float64Array, JsError> {
+        match &self.0 {
+            Value::Num(array) => Ok(unsafe { Float64Array::view(&array.data) }),
+            _ => Err(JsError::new("Only number arrays can be viewed as numbers")),
+        }
+    }
+
+    /// A `Uint8Array` over the bytes of the value, directly in wasm memory.
+    ///
+    /// The same lifetime rules as for `viewNumbers` apply.
+    #[wasm_bindgen(js_name = viewBytes)]
+    pub fn view_bytes(&self) -> Result<Uint8Array, JsError> {
+        match &self.0 {
+            Value::Byte(array) => Ok(unsafe { Uint8Array::view(&array.data) }),
+            _ => Err(JsError::new("Only byte arrays can be viewed as bytes")),
+        }
+    }
+
+    /// All of the characters of a character array as one string, in row-major order.
+    pub fn text(&self) -> Result<String, JsError> {
+        match &self.0 {
+            Value::Char(array) => Ok(array.elements().collect()),
+            _ => Err(JsError::new("Only character arrays can be read as text")),
+        }
+    }
+
     #[wasm_bindgen(js_name = type)]
     pub fn type_(&self) -> String {
-        match self.to_value() {
-            Value::Byte(_) | Value::Num(_) => "number".to_string(),
+        match &self.0 {
+            Value::Byte(_) => "byte".to_string(),
+            Value::Num(_) => "number".to_string(),
             Value::Char(_) => "char".to_string(),
             Value::Complex(_) => "complex".to_string(),
             Value::Box(_) => "box".to_string(),
         }
     }
 
+    /// Whether every element of the value is either 0 or 1, which is also true for empty arrays.
+    /// Unlike `encode`, this doesn't look at the boolean flag of the array.
+    #[wasm_bindgen(js_name = isBoolean)]
+    pub fn is_boolean(&self) -> bool {
+        match &self.0 {
+            Value::Byte(array) => array.data.iter().all(|&b| b <= 1),
+            Value::Num(array) => array.data.iter().all(|&n| n == 0.0 || n == 1.0),
+            _ => false,
+        }
+    }
+
+    pub fn info(&self) -> JsValue {
+        let info = ValueInfo {
+            type_: self.type_(),
+            rank: self.0.rank(),
+            shape: self.shape(),
+            element_count: self.0.element_count(),
+            is_boolean: self.is_boolean(),
+            label: self.label(),
+            has_map_keys: self.0.meta().map_keys.is_some(),
+        };
+
+        serde_wasm_bindgen::to_value(&info).unwrap()
+    }
+
+    /// Whether the values match, the same way the `match` primitive compares them.
+    pub fn equals(&self, other: &NativeValueWrapper) -> bool {
+        self.0 == other.0
+    }
+
+    /// Compares the values in the order the sorting primitives use, returning -1, 0 or 1.
+    pub fn compare(&self, other: &NativeValueWrapper) -> i32 {
+        match self.0.cmp(&other.0) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        }
+    }
+
+    /// A hash of the value as a hexadecimal string, which is the same for values that match.
+    /// It doesn't depend on the wasm instance, so it can be used to cache values across workers.
+    /// It is computed from Uiua's own hashing of values, so it may change when Uiua is upgraded
+    /// and shouldn't be persisted across versions.
+    pub fn hash(&self) -> String {
+        let mut hasher = FnvHasher::default();
+        self.0.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
     pub fn show(&self) -> String {
         self.0.show()
     }
@@ -82,8 +619,13 @@ impl NativeValueWrapper {
         match output {
             SmartOutput::Normal(value) => {
                 Reflect::set(&object, &JsValue::from("type"), &JsValue::from("normal")).unwrap();
-                Reflect::set(&object, &JsValue::from("value"), &JsValue::from(NativeValueWrapper(value))).unwrap();
-            },
+                Reflect::set(
+                    &object,
+                    &JsValue::from("value"),
+                    &JsValue::from(NativeValueWrapper(value)),
+                )
+                .unwrap();
+            }
             SmartOutput::Png(vec, label) => {
                 Reflect::set(&object, &JsValue::from("type"), &JsValue::from("png")).unwrap();
                 Reflect::set(&object, &JsValue::from("value"), &JsValue::from(vec)).unwrap();
@@ -104,3 +646,158 @@ impl NativeValueWrapper {
         object
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes_map_strings_whatever_their_lengths() {
+        let env = Uiua::with_safe_sys();
+        let build = |keys: [&str; 2]| {
+            let keys = keys.iter().map(|&key| Value::from(key)).collect();
+            build_map(keys, vec![Value::from(1.0), Value::from(2.0)], &env).unwrap()
+        };
+
+        for map in [build(["ab", "cd"]), build(["a", "bc"])] {
+            let keys = map.meta().map_keys.clone().unwrap().normalized();
+            assert!(matches!(keys, Value::Box(_)));
+            assert_eq!(keys.row_count(), 2);
+            assert!(matches!(map, Value::Num(_)));
+        }
+    }
+
+    #[test]
+    fn keeps_scalars_of_one_type_unboxed() {
+        let env = Uiua::with_safe_sys();
+        let rows = vec![Value::from(1.0), Value::from(2.0)];
+        assert!(matches!(rows_to_value(rows, &env).unwrap(), Value::Num(_)));
+
+        let rows = vec![Value::from(1.0), Value::from("a")];
+        assert!(matches!(rows_to_value(rows, &env).unwrap(), Value::Box(_)));
+    }
+
+    fn wrap(value: Value) -> NativeValueWrapper {
+        NativeValueWrapper::new(value)
+    }
+
+    #[test]
+    fn bytes_and_numbers_with_the_same_elements_match() {
+        let bytes = wrap([1u8, 2, 3].iter().copied().collect::<Value>());
+        let numbers = wrap([1.0, 2.0, 3.0].iter().copied().collect::<Value>());
+
+        assert_eq!(bytes.type_(), "byte");
+        assert_eq!(numbers.type_(), "number");
+        assert!(bytes.equals(&numbers));
+        assert_eq!(bytes.hash(), numbers.hash());
+        assert_eq!(bytes.compare(&numbers), 0);
+    }
+
+    #[test]
+    fn compare_agrees_with_equals() {
+        let values = [
+            wrap([1.0, 2.0].iter().copied().collect::<Value>()),
+            wrap([1u8, 2].iter().copied().collect::<Value>()),
+            wrap([1.0, 3.0].iter().copied().collect::<Value>()),
+            wrap(Value::from(1.0)),
+            wrap(Value::from("ab")),
+            wrap(Value::from("ab")),
+        ];
+
+        for a in &values {
+            for b in &values {
+                assert_eq!(
+                    a.equals(b),
+                    a.compare(b) == 0,
+                    "{} and {}",
+                    a.show(),
+                    b.show()
+                );
+                assert_eq!(a.compare(b), -b.compare(a), "{} and {}", a.show(), b.show());
+                if a.equals(b) {
+                    assert_eq!(a.hash(), b.hash(), "{} and {}", a.show(), b.show());
+                }
+            }
+        }
+
+        assert_ne!(values[0].hash(), values[2].hash());
+    }
+
+    fn csv_options(delimiter: char) -> CsvOptions {
+        CsvOptions {
+            delimiter: Some(delimiter),
+        }
+    }
+
+    fn cells(value: &Value) -> Vec<Vec<String>> {
+        value
+            .rows()
+            .map(|row| row.rows().map(|cell| csv_cell(&cell)).collect())
+            .collect()
+    }
+
+    fn numbers(numbers: &[f64]) -> Value {
+        numbers.iter().copied().collect()
+    }
+
+    #[test]
+    fn pads_short_csv_rows() {
+        let value = csv_to_value("a,b,c\nd\n", &CsvOptions::default()).unwrap();
+
+        assert_eq!(value.shape().to_vec(), [2, 3]);
+        assert_eq!(cells(&value), [["a", "b", "c"], ["d", "", ""]]);
+    }
+
+    #[test]
+    fn parses_csv_with_a_delimiter() {
+        let value = csv_to_value("a;\"b;c\"\n1;2\n", &csv_options(';')).unwrap();
+        assert_eq!(cells(&value), [["a", "b;c"], ["1", "2"]]);
+
+        let value = csv_to_value("a;b\n", &CsvOptions::default()).unwrap();
+        assert_eq!(cells(&value), [["a;b"]]);
+
+        assert!(csv_to_value("a", &csv_options('→')).is_err());
+    }
+
+    #[test]
+    fn writes_a_char_matrix_as_one_column_of_strings() {
+        let env = Uiua::with_safe_sys();
+        let matrix = Value::from_row_values(vec![Value::from("ab"), Value::from("cd")], &env);
+
+        let csv = value_to_csv(&matrix.unwrap(), &CsvOptions::default()).unwrap();
+        assert_eq!(csv, "ab\ncd\n");
+    }
+
+    #[test]
+    fn writes_numbers_without_uiua_formatting() {
+        let env = Uiua::with_safe_sys();
+        let table = Value::from_row_values(vec![numbers(&[1.0, -1.0]), numbers(&[2.5, 3.0])], &env);
+
+        let csv = value_to_csv(&table.unwrap(), &CsvOptions::default()).unwrap();
+        assert_eq!(csv, "1,-1\n2.5,3\n");
+
+        let csv = value_to_csv(&numbers(&[-4.0]), &CsvOptions::default()).unwrap();
+        assert_eq!(csv, "-4\n");
+    }
+
+    #[test]
+    fn round_trips_csv_with_a_delimiter() {
+        let options = csv_options(';');
+        let table = csv_to_value("name;note\nA;x;y\nB;\"1, 2\"\n", &options).unwrap();
+
+        let csv = value_to_csv(&table, &options).unwrap();
+        assert_eq!(csv, "name;note;\nA;x;y\nB;1, 2;\n");
+
+        let parsed = csv_to_value(&csv, &options).unwrap();
+        assert!(NativeValueWrapper::new(parsed).equals(&NativeValueWrapper::new(table)));
+    }
+
+    #[test]
+    fn rejects_values_of_rank_3() {
+        let env = Uiua::with_safe_sys();
+        let row = Value::from_row_values(vec![numbers(&[1.0]), numbers(&[2.0])], &env).unwrap();
+        let cube = Value::from_row_values(vec![row.clone(), row], &env).unwrap();
+
+        assert!(value_to_csv(&cube, &CsvOptions::default()).is_err());
+    }
+}
@@ -6,4 +6,33 @@ export abstract class AbstractBackend {
     printStrStderr(str: string) {
         throw new Error("Printing to stderr is not supported in this environment.");
     }
-}
\ No newline at end of file
+
+    /**
+     * Block for the given number of seconds.
+     * 
+     * Uiua code runs synchronously, so this can't return a promise. In a worker, it can be
+     * implemented with `blockingSleep`.
+     */
+    sleep(seconds: number) {
+        throw new Error("Sleeping is not supported in this environment.");
+    }
+
+    /**
+     * The current time in seconds since the Unix epoch. Override to provide a fixed clock.
+     * 
+     * The execution limit is measured with this clock too, so it has to keep advancing
+     * when a limit is set.
+     */
+    now(): number {
+        return Date.now() / 1000;
+    }
+}
+
+/**
+ * Block the current thread for the given number of seconds using `Atomics.wait`.
+ * Browsers only allow this in workers, and it requires `SharedArrayBuffer` to be available.
+ */
+export function blockingSleep(seconds: number) {
+    const cell = new Int32Array(new SharedArrayBuffer(4));
+    Atomics.wait(cell, 0, 0, seconds * 1000);
+}
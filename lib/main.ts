@@ -1,6 +1,6 @@
 import init from "../crate/pkg/uiua_js";
 
-export { UiuaRuntime } from "./runtime";
+export { UiuaRuntime, UiuaDebugSession, listBindings, getConstant } from "./runtime";
 export { UiuaValue } from "./value";
 
 // @ts-ignore
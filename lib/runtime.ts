@@ -1,8 +1,11 @@
 import {
     CompilerRef,
+    DebugSessionInternal,
+    UiuaFunctionInternal,
     UiuaRef,
     UiuaRuntimeInternal,
     runCode,
+    startDebugSession,
 } from "../crate/pkg/uiua_js";
 import { AbstractBackend } from "./backend";
 
@@ -26,8 +29,82 @@ class Uiua {
     push(value: UiuaValue) {
         this.ref.push(value.internalWrapper);
     }
+
+    /**
+     * Get a value from the stack without popping it.
+     * 
+     * @param depth How far below the top of the stack the value is, `0` being the top.
+     */
+    peek(depth: number = 0) {
+        return UiuaValue.fromWrapper(this.ref.peek(depth));
+    }
+
+    stackSize(): number {
+        return this.ref.stackSize();
+    }
+
+    /**
+     * Pop multiple values, with the top of the stack first.
+     */
+    popMany(count: number): UiuaValue[] {
+        return this.ref.popMany(count).map(UiuaValue.fromWrapper);
+    }
+
+    popNumber(): number {
+        return this.ref.popNumber();
+    }
+
+    popString(): string {
+        return this.ref.popString();
+    }
+
+    popBytes(): Uint8Array {
+        return this.ref.popBytes();
+    }
+
+    pushNumber(number: number) {
+        this.ref.pushNumber(number);
+    }
+
+    pushString(string: string) {
+        this.ref.pushString(string);
+    }
+
+    pushBytes(bytes: Uint8Array | number[]) {
+        this.ref.pushBytes(Uint8Array.from(bytes));
+    }
+
+    /**
+     * The span of the code that called the binding.
+     */
+    get span(): Span {
+        return this.ref.span;
+    }
+
+    /**
+     * Fail the binding with a Uiua error pointing at the code that called it.
+     */
+    throwError(message: string): never {
+        this.ref.throwError(message);
+        throw new Error(message);
+    }
 }
 
+/**
+ * A Uiua function that can be called from JavaScript.
+ */
+type UiuaFunction = ((...args: UiuaValue[]) => UiuaValue[]) & {
+    inputs: number;
+    outputs: number;
+    /**
+     * Free the interpreter of the function. It is also freed once the function is garbage collected,
+     * but that may happen much later.
+     */
+    dispose(): void;
+};
+
+const functionRegistry = new FinalizationRegistry((internal: UiuaFunctionInternal) => internal.free());
+
 /**
  * The result after Uiua code is executed.
  */
@@ -37,6 +114,15 @@ interface UiuaExecutionResult {
     stdout: Uint8Array;
     stderr: Uint8Array;
     diagnostics: UiuaDiagnostic[];
+    trace: UiuaTraceEntry[];
+}
+
+/**
+ * The state of the stack after a top level instruction was executed.
+ */
+interface UiuaTraceEntry {
+    span?: Span;
+    stack: UiuaValue[];
 }
 
 interface DocumentLocation {
@@ -81,6 +167,118 @@ interface UiuaDiagnostic {
     span: Span;
 }
 
+interface UiuaBindingDocs {
+    doc?: string;
+    parameters?: string[];
+    example?: string;
+}
+
+interface UiuaHostBindingInfo extends UiuaBindingDocs {
+    name: string;
+    kind: "constant" | "function";
+    signature: { inputs: number; outputs: number };
+}
+
+interface UiuaBindingInfo {
+    name: string;
+    kind: "constant" | "function" | "module" | "macro";
+    signature?: { inputs: number; outputs: number };
+    comment?: string;
+    span: Span;
+    public: boolean;
+}
+
+/**
+ * List the top level bindings defined in the code compiled by the given compiler.
+ * Bindings of imported modules, packages and host namespaces are not included.
+ * 
+ * @param compiler The compiler returned from an execution.
+ */
+export function listBindings(compiler: CompilerRef): UiuaBindingInfo[] {
+    return compiler.bindings();
+}
+
+/**
+ * Get the value of a constant binding defined in the code compiled by the given compiler.
+ * 
+ * @param compiler The compiler returned from an execution.
+ * @param name The name of the constant.
+ */
+export function getConstant(compiler: CompilerRef, name: string): UiuaValue | undefined {
+    const value = compiler.constantValue(name);
+    return value ? UiuaValue.fromWrapper(value) : undefined;
+}
+
+interface UiuaTraceFrame {
+    line: string;
+    span: Span;
+}
+
+/**
+ * A paused execution of Uiua code that can be stepped through.
+ */
+export class UiuaDebugSession {
+    constructor(private internal: DebugSessionInternal) {}
+
+    /**
+     * Whether all of the code has been executed.
+     */
+    get finished(): boolean {
+        return this.internal.finished;
+    }
+
+    /**
+     * The span of the code that will be executed next, if any.
+     */
+    get span(): Span | undefined {
+        return this.internal.span;
+    }
+
+    /**
+     * The functions that were stepped into, innermost first.
+     */
+    get callStack(): UiuaTraceFrame[] {
+        return this.internal.callStack;
+    }
+
+    get stack(): UiuaValue[] {
+        return this.internal.stack.map(UiuaValue.fromWrapper);
+    }
+
+    setBreakpoint(location: DocumentLocation) {
+        this.internal.setBreakpoint(location);
+    }
+
+    removeBreakpoint(location: DocumentLocation) {
+        this.internal.removeBreakpoint(location);
+    }
+
+    clearBreakpoints() {
+        this.internal.clearBreakpoints();
+    }
+
+    /**
+     * Execute the next piece of code, running function calls to completion.
+     */
+    stepOver() {
+        this.internal.stepOver();
+    }
+
+    /**
+     * Execute the next piece of code, entering the function if it is a call.
+     */
+    stepInto() {
+        this.internal.stepInto();
+    }
+
+    /**
+     * Run until a breakpoint is reached or the execution finishes.
+     */
+    continue() {
+        this.internal.continue();
+    }
+}
+
 /**
  * The context for running Uiua code.
  */
@@ -103,7 +301,7 @@ export class UiuaRuntime {
     ): UiuaExecutionResult {
         const result = runCode(
             code,
-            initialValues.map(value => value.internalWrapper),
+            initialValues.map(value => value.ownedWrapper),
             this.internal
         );
 
@@ -113,21 +311,107 @@ export class UiuaRuntime {
             stdout: result.stdout,
             stderr: result.stderr,
             diagnostics: result.diagnostics,
+            trace: result.trace.map((entry: any) => ({
+                span: entry.span,
+                stack: entry.stack.map(UiuaValue.fromWrapper),
+            })),
         };
     }
 
+    /**
+     * Compile Uiua code and start a debugging session for it without executing anything.
+     * 
+     * @param code The Uiua code to debug.
+     * @param initialValues The initial values to start the stack with.
+     */
+    debugString(
+        code: string,
+        initialValues: UiuaValue[] = [],
+    ): UiuaDebugSession {
+        const session = startDebugSession(
+            code,
+            initialValues.map(value => value.ownedWrapper),
+            this.internal
+        );
+
+        return new UiuaDebugSession(session);
+    }
+
     /**
      * Add a custom binding to the runtime. Allows calling JavaScript code from Uiua runtime.
      * 
-     * @param name The name of the binding.
+     * Throws if the name is reserved, if a binding or constant with the name was already added, or if
+     * a namespaced or documented name isn't made of capitalized identifiers.
+     * 
+     * @param name The name of the binding. Use `~` to place it in a module, like `Host~Fetch`.
+     * Every part of a namespaced name has to be a capitalized identifier.
      * @param inputs The number of inputs the binding takes.
      * @param outputs The number of outputs the binding produces.
      * @param callback The callback to run when the binding is called.
+     * @param docs Documentation of the binding, which becomes its doc comment.
      */
-    addBinding(name: string, inputs: number, outputs: number, callback: (uiua: Uiua) => void) {
+    addBinding(
+        name: string,
+        inputs: number,
+        outputs: number,
+        callback: (uiua: Uiua) => void,
+        docs?: UiuaBindingDocs,
+    ) {
         this.internal.addBinding(name, inputs, outputs, (ref: UiuaRef) => {
             callback(new Uiua(ref));
-        });
+        }, docs);
+    }
+
+    /**
+     * List the functions and constants that were added to the runtime.
+     */
+    listHostBindings(): UiuaHostBindingInfo[] {
+        return this.internal.listHostBindings();
+    }
+
+    /**
+     * Add a named constant to the runtime, which Uiua code can refer to like any other constant.
+     * 
+     * Throws if the name is reserved, isn't made of capitalized identifiers, or a binding or constant
+     * with the name was already added.
+     * 
+     * @param name The name of the constant. Use `~` to place it in a module, like `Host~Version`.
+     * Every part of the name has to be a capitalized identifier.
+     * @param value The value of the constant.
+     */
+    addConstant(name: string, value: UiuaValue) {
+        this.internal.addConstant(name, value.internalWrapper);
+    }
+
+    /**
+     * Register a module that Uiua code can import with `~ "path"`.
+     * 
+     * @param path The path the module is imported by.
+     * @param source The Uiua source code of the module.
+     */
+    addModule(path: string, source: string) {
+        this.internal.addModule(path, source);
+    }
+
+    /**
+     * Add a git package to the package cache, making it importable with `~ "git: url"`.
+     * 
+     * @param url The URL of the package repository.
+     * @param version The branch or commit the package is imported with, if any.
+     * @param files The files of the package by their path relative to the repository root,
+     * which must include the `lib.ua` entry point.
+     */
+    addPackage(url: string, version: string | undefined, files: Record<string, string> | Map<string, string>) {
+        this.internal.addPackage(url, version, files);
+    }
+
+    /**
+     * Set a callback that provides the source of modules that were not registered with `addModule`.
+     * 
+     * @param resolver Returns the source of the module at the given path, or `undefined` if it doesn't exist.
+     */
+    setModuleResolver(resolver: (path: string) => string | undefined) {
+        this.internal.setModuleResolver(resolver);
     }
 
     /**
@@ -139,6 +423,72 @@ export class UiuaRuntime {
         this.internal.setCompiler(compiler);
     }
 
+    /**
+     * Call a function defined in previously executed code, without running the code again.
+     * 
+     * @param compiler The compiler returned from an execution.
+     * @param name The name of the function.
+     * @param args The arguments in the order of the function's parameters, the first one is put on top of the stack.
+     * @returns The outputs of the function, the top of the stack first.
+     */
+    callFunction(compiler: CompilerRef, name: string, args: UiuaValue[] = []): UiuaValue[] {
+        return this.internal
+            .callFunction(compiler, name, args.map(value => value.ownedWrapper))
+            .map(UiuaValue.fromWrapper);
+    }
+
+    /**
+     * Get a function defined in previously executed code as a JavaScript function. The returned function
+     * keeps its own interpreter, so calling it repeatedly doesn't compile or run the code again.
+     * 
+     * @param compiler The compiler returned from an execution.
+     * @param name The name of the function.
+     * Only named functions can be wrapped. Uiua values can't hold functions, so there are no boxed
+     * functions on the stack to wrap.
+     * 
+     * @returns A function taking the arguments in the order of the Uiua function's parameters and
+     * returning its outputs, the top of the stack first. Call `dispose` on it once it is no longer needed.
+     */
+    getFunction(compiler: CompilerRef, name: string): UiuaFunction {
+        const internal = this.internal.getFunction(compiler, name);
+        const callable = (...args: UiuaValue[]) => internal
+            .call(args.map(value => value.ownedWrapper))
+            .map(UiuaValue.fromWrapper);
+
+        const dispose = () => {
+            if (functionRegistry.unregister(callable)) {
+                internal.free();
+            }
+        };
+
+        functionRegistry.register(callable, internal, callable);
+        return Object.assign(callable, {
+            inputs: internal.inputs,
+            outputs: internal.outputs,
+            dispose,
+        });
+    }
+
+    /**
+     * Serialize the state of a compiler, so it can be stored and restored later with `restoreCompiler`.
+     * 
+     * @param compiler The compiler to serialize.
+     */
+    serializeCompiler(compiler: CompilerRef): Uint8Array {
+        return compiler.serialize();
+    }
+
+    /**
+     * Restore a compiler from bytes created with `serializeCompiler`. The code is compiled again
+     * with the bindings of this runtime, but not executed. Imported files and packages are part of
+     * the snapshot, but the host bindings and constants have to match the ones it was saved with.
+     * 
+     * @param bytes The serialized compiler state.
+     */
+    restoreCompiler(bytes: Uint8Array): CompilerRef {
+        return this.internal.restoreCompiler(bytes);
+    }
+
     /**
      * Set a custom backend to use for execution.
      * 
@@ -148,10 +498,46 @@ export class UiuaRuntime {
         let internalBackend = this.internal.getBackend();
         internalBackend = internalBackend.with_print_str_stdout_handler(backend.printStrStdout.bind(backend));
         internalBackend = internalBackend.with_print_str_stderr_handler(backend.printStrStderr.bind(backend));
+        internalBackend = internalBackend.with_now_handler(backend.now.bind(backend));
+        internalBackend = internalBackend.with_sleep_handler(backend.sleep.bind(backend));
         this.internal.setBackend(internalBackend);
     }
 
+    /**
+     * Stop the execution with an error once it takes longer than the given time.
+     * This can't be combined with `setVirtualTime`, which stops the clock the limit is measured with.
+     * 
+     * @param seconds The maximum time the code may run for.
+     */
     setExecutionLimit(seconds: number) {
         this.internal.setExecutionLimitSeconds(seconds);
     }
+
+    /**
+     * Use a simulated clock that is only advanced by sleeping, so sleeps finish instantly.
+     * Running code fails if an execution limit is set as well.
+     * 
+     * @param start The time in seconds the clock starts at for each run.
+     */
+    setVirtualTime(start: number = 0) {
+        this.internal.setBackend(this.internal.getBackend().with_virtual_time(start));
+    }
+
+    /**
+     * Seed the random number generator before each run, making random values reproducible.
+     * 
+     * @param seed The seed to use.
+     */
+    setRandomSeed(seed: number | bigint) {
+        this.internal.setRandomSeed(BigInt(seed));
+    }
+
+    /**
+     * Record the stack after each top level instruction, available as `trace` in the execution result.
+     * 
+     * @param limit The maximum number of trace entries to keep.
+     */
+    setTraceLimit(limit: number) {
+        this.internal.setTraceLimit(limit);
+    }
 }
\ No newline at end of file
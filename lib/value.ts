@@ -28,7 +28,21 @@ interface SmartValueNormal extends SmartValueBase {
 
 export type SmartValue = SmartValuePng | SmartValueGif | SmartValueWav | SmartValueNormal
 
-type UiuaType = "char" | "number" | "complex" | "box";
+type UiuaType = "char" | "byte" | "number" | "complex" | "box";
+
+/**
+ * A summary of a value. There is no fill value, because arrays don't carry one in Uiua;
+ * fills only exist while a `⬚` modifier is running.
+ */
+export interface ValueInfo {
+    type: UiuaType;
+    rank: number;
+    shape: number[];
+    elementCount: number;
+    isBoolean: boolean;
+    label?: string;
+    hasMapKeys: boolean;
+}
 
 export class UiuaValue {
     private constructor(
@@ -44,14 +58,108 @@ export class UiuaValue {
         return Array.from(this.internal.shape());
     }
 
+    /**
+     * The type of the elements. Byte arrays are reported as `"byte"`, not `"number"`.
+     */
     get type(): UiuaType {
         return this.internal.type() as any;
     }
 
+    /**
+     * Whether every element is either 0 or 1, which is also true for empty arrays.
+     * This doesn't look at the boolean flag Uiua sets on the results of comparisons,
+     * which is what `encode` keeps.
+     */
+    get isBoolean(): boolean {
+        return this.internal.isBoolean();
+    }
+
+    info(): ValueInfo {
+        return this.internal.info();
+    }
+
     get internalWrapper(): NativeValueWrapper {
         return this.internal;
     }
 
+    /**
+     * A copy of the internal wrapper, for passing the value to functions that take ownership of it
+     * while keeping this value usable.
+     */
+    get ownedWrapper(): NativeValueWrapper {
+        return this.internal.clone();
+    }
+
+    /**
+     * A copy of the value. The data is shared until one of the copies is modified, so this is cheap.
+     */
+    clone(): UiuaValue {
+        return new UiuaValue(this.internal.clone());
+    }
+
+    /**
+     * A view of the numbers of a number array, without copying them.
+     * 
+     * The view is only valid until wasm memory grows, which can happen on any other call into Uiua.
+     * Use `numbers()` for a copy that is always safe to keep.
+     */
+    viewNumbers(): Float64Array {
+        return this.internal.viewNumbers();
+    }
+
+    /**
+     * A copy of the numbers of a number array, in row-major order.
+     */
+    numbers(): Float64Array {
+        return this.internal.viewNumbers().slice();
+    }
+
+    /**
+     * A view of the bytes of a byte array, without copying them. The same rules as for `viewNumbers` apply.
+     */
+    viewBytes(): Uint8Array {
+        return this.internal.viewBytes();
+    }
+
+    /**
+     * A copy of the bytes of a byte array, in row-major order.
+     */
+    bytes(): Uint8Array {
+        return this.internal.viewBytes().slice();
+    }
+
+    /**
+     * All characters of a character array as a single string, in row-major order.
+     */
+    text(): string {
+        return this.internal.text();
+    }
+
+    /**
+     * Whether the values match, the same way Uiua's `match` function compares them.
+     */
+    equals(other: UiuaValue): boolean {
+        return this.internal.equals(other.internal);
+    }
+
+    /**
+     * Compare the values in the order Uiua sorts them.
+     * 
+     * @returns -1 if this value comes first, 1 if the other one does, or 0 if they are equal.
+     */
+    compare(other: UiuaValue): number {
+        return this.internal.compare(other.internal);
+    }
+
+    /**
+     * A hash of the value that is the same for values that match, even across workers.
+     * It can be used as a key for caching values, but different values may share a hash.
+     * The hash may change between versions of this library, so don't persist it.
+     */
+    hash(): string {
+        return this.internal.hash();
+    }
+
     show(): string {
         return this.internal.show();
     }
@@ -60,9 +168,211 @@ export class UiuaValue {
         return this.internal.smartValue();
     }
 
+    /**
+     * Convert the value into nested JavaScript arrays with its shape applied.
+     * 
+     * @param options How to convert boxes, complex numbers, characters and maps.
+     */
+    toJs(options: ToJsOptions = {}): any {
+        const result = this.internal.toJs(options);
+        return options.deep ? result : wrapNested(result);
+    }
+
+    /**
+     * Encode the value as JSON the same way Uiua's `json` function does, as a JavaScript value.
+     * This also makes `JSON.stringify` work on Uiua values.
+     */
+    toJSON(): any {
+        return this.internal.toJSON();
+    }
+
+    /**
+     * Encode the value in a compact binary format that keeps its type, shape, label, map keys
+     * and the boolean flag Uiua sets on the results of comparisons.
+     * The result can be stored or sent to a worker and turned back into a value with `decode`.
+     */
+    encode(): Uint8Array {
+        return this.internal.encode();
+    }
+
+    /**
+     * Encode the value into an `ArrayBuffer` that can be transferred to or from a worker
+     * without copying it, and turned back into a value there with `fromTransferable`.
+     * 
+     * @example
+     * const buffer = value.toTransferable();
+     * worker.postMessage(buffer, [buffer]);
+     */
+    toTransferable(): ArrayBuffer {
+        return this.internal.toTransferable();
+    }
+
+    /**
+     * Encode a list or table as CSV. Strings and numbers are written as plain text, other cells
+     * the way Uiua shows them. The result can be read back with `fromCsv` using the same delimiter.
+     * 
+     * @param options The delimiter to use, a comma by default.
+     */
+    toCsv(options: { delimiter?: string } = {}): string {
+        return this.internal.toCsv(options);
+    }
+
+    /**
+     * Encode the value as an Arrow IPC stream. The value must be a map of column names to columns,
+     * or a rank 2 numeric or boxed array whose columns are named by their index.
+     * 
+     * This requires a build with the `arrow` feature, see `npm run build:arrow`.
+     */
+    toArrow(): Uint8Array {
+        requireArrow();
+        return (this.internal as any).toArrow();
+    }
+
+    /**
+     * Convert a map value to a JavaScript `Map`. Strings and scalar numbers become JavaScript
+     * strings and numbers, any other keys and values stay Uiua values.
+     */
+    toMap(): Map<MapEntry, MapEntry> {
+        const result = new Map<MapEntry, MapEntry>();
+        this.internal.toMap().forEach((value: any, key: any) => {
+            result.set(fromMapEntry(key), fromMapEntry(value));
+        });
+        return result;
+    }
+
     static fromWrapper(internal: NativeValueWrapper): UiuaValue {
         return new UiuaValue(internal);
     }
+
+    static fromNumber(number: number): UiuaValue {
+        return new UiuaValue(NativeValueWrapper.fromNumber(number));
+    }
+
+    static fromString(string: string): UiuaValue {
+        return new UiuaValue(NativeValueWrapper.fromString(string));
+    }
+
+    /**
+     * Decode JSON the same way Uiua's `°json` does.
+     * 
+     * @param json JSON text, or a JavaScript value that is converted to JSON first.
+     */
+    static fromJSON(json: any): UiuaValue {
+        const text = typeof json === "string" ? json : JSON.stringify(json);
+        return new UiuaValue(NativeValueWrapper.fromJSON(text));
+    }
+
+    /**
+     * Decode a value created with `encode`.
+     */
+    static decode(bytes: Uint8Array): UiuaValue {
+        return new UiuaValue(NativeValueWrapper.decode(bytes));
+    }
+
+    /**
+     * Reconstruct a value from a buffer created with `toTransferable`, usually in another worker.
+     */
+    static fromTransferable(buffer: ArrayBuffer): UiuaValue {
+        return new UiuaValue(NativeValueWrapper.fromTransferable(buffer));
+    }
+
+    /**
+     * Parse CSV into a table of boxed strings, padding short rows with empty strings.
+     * 
+     * @param text The CSV text.
+     * @param options The delimiter to use, a comma by default.
+     */
+    static fromCsv(text: string, options: { delimiter?: string } = {}): UiuaValue {
+        return new UiuaValue(NativeValueWrapper.fromCsv(text, options));
+    }
+
+    /**
+     * Decode an Arrow IPC stream or file into a map of boxed column names to boxed columns.
+     * 
+     * Missing numbers and booleans become NaN and missing strings become empty strings.
+     * 64-bit integers that can't be represented exactly as numbers are an error.
+     * This requires a build with the `arrow` feature, see `npm run build:arrow`.
+     */
+    static fromArrow(bytes: Uint8Array): UiuaValue {
+        requireArrow();
+        return new UiuaValue((NativeValueWrapper as any).fromArrow(bytes));
+    }
+
+    /**
+     * Build a Uiua map from a JavaScript `Map` or object. Strings and other arrays are boxed, like
+     * `°json` does, so Uiua code looks keys up with `get □"key"`.
+     */
+    static fromMap(map: Map<MapEntry, MapEntry> | Record<string, MapEntry>): UiuaValue {
+        const entries = map instanceof Map ? Array.from(map.entries()) : Object.entries(map);
+        const keys = entries.map(([key]) => toMapWrapper(key));
+        const values = entries.map(([, value]) => toMapWrapper(value));
+        return new UiuaValue(NativeValueWrapper.fromMap(keys, values));
+    }
+}
+
+export interface ToJsOptions {
+    /** Convert the contents of boxes too, instead of leaving them as Uiua values. */
+    deep?: boolean;
+    /** Represent complex numbers as `[re, im]` (default) or `{ re, im }`. */
+    complex?: "pair" | "object";
+    /** Turn the last axis of character arrays into strings (default) or keep single characters. */
+    chars?: "string" | "char";
+    /** Represent maps as plain objects (default) or as `Map`s. */
+    maps?: "object" | "map";
+}
+
+function wrapNested(value: any): any {
+    if (value instanceof NativeValueWrapper) {
+        return UiuaValue.fromWrapper(value);
+    }
+
+    if (Array.isArray(value)) {
+        return value.map(wrapNested);
+    }
+
+    if (value instanceof Map) {
+        const result = new Map();
+        value.forEach((entry, key) => result.set(wrapNested(key), wrapNested(entry)));
+        return result;
+    }
+
+    if (typeof value === "object" && value !== null) {
+        return Object.fromEntries(Object.entries(value).map(([key, entry]) => [key, wrapNested(entry)]));
+    }
+
+    return value;
+}
+
+type MapEntry = string | number | UiuaValue;
+
+function requireArrow() {
+    if (!("fromArrow" in NativeValueWrapper)) {
+        throw new Error("Arrow support is not included in this build, it requires the `arrow` feature.");
+    }
+}
+
+/**
+ * The wrapper to build a map from. Values given by the caller are copied, because building
+ * the map takes ownership of the wrappers.
+ */
+function toMapWrapper(entry: MapEntry): NativeValueWrapper {
+    if (typeof entry === "string") {
+        return NativeValueWrapper.fromString(entry);
+    }
+
+    if (typeof entry === "number") {
+        return NativeValueWrapper.fromNumber(entry);
+    }
+
+    return entry.ownedWrapper;
+}
+
+function fromMapEntry(entry: any): MapEntry {
+    if (entry instanceof NativeValueWrapper) {
+        return UiuaValue.fromWrapper(entry);
+    }
+
+    return entry;
 }
 
 function reshapeArray(array: any, shape: number[], type: string): any {